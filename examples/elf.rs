@@ -1,11 +1,12 @@
 use pwn::*;
 
-fn main() {
+fn main() -> Result<(), Error> {
     init_logger();
-    let mut e = Elf::new("/tmp/binary");
+    let mut e = Elf::open("/tmp/binary")?;
     info!("Symbols:");
-    for (&name, addr) in e.symbols().iter().filter(|(&n, _)| !n.contains("__")) {
+    for (name, addr) in e.symbols().iter().filter(|(n, _)| !n.contains("__")) {
         info!("{name:<30}: {addr:#012x}");
     }
     e.set_address(0x5000);
+    Ok(())
 }
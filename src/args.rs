@@ -0,0 +1,165 @@
+//! Pwntools-style "magic" command line arguments.
+//!
+//! Any argument made up of uppercase letters, digits and underscores, optionally followed by
+//! `=value`, is collected into a map, as are environment variables starting with `PWNLIB_`.
+//! Arguments take precedence over the environment.
+//! ```sh
+//! ./exploit REMOTE DEBUG HOST=10.10.10.10 PORT=1337
+//! PWNLIB_NOTERM=1 ./exploit
+//! ```
+//! ```no_run
+//! use pwn::{args, Remote};
+//! let args = args();
+//! if args.flag("REMOTE") {
+//!     let host = args.value("HOST").unwrap_or("127.0.0.1");
+//!     let port: u16 = args.get("PORT").unwrap_or(1337);
+//!     let r = Remote::new(host, port).unwrap();
+//! }
+//! ```
+//! Some arguments also configure the [`context`] and logging. They are applied by [`init`], which
+//! [`init_logger`](crate::init_logger) calls, and never by [`args`]. Call one of these at the start
+//! of `main`, on the main thread, so that threads spawned afterwards pick up the settings.
+//! * `LOG_LEVEL=<level>` - Set the log level, e.g. `debug` or `warning`
+//! * `DEBUG` - Set the log level to `debug`
+//! * `SILENT` - Set the log level to `error`
+//! * `TIMEOUT=<seconds>` - Set the default tube timeout. Values which aren't a non-negative number
+//!   are ignored with a warning
+//! * `NOTERM` - Disable coloured output
+//!
+//! `LOG_FILE=<path>` is read by [`init_logger`](crate::init_logger).
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::str::FromStr;
+use std::sync::Once;
+use std::time::Duration;
+
+use once_cell::sync::Lazy;
+use tracing::Level;
+
+use crate::context::{self, Context};
+use crate::{logging, warn};
+
+static ARGS: Lazy<Args> = Lazy::new(|| {
+    let lossy = |s: OsString| s.to_string_lossy().into_owned();
+    Args::parse_from(
+        std::env::args_os().skip(1).map(lossy),
+        std::env::vars_os().map(|(k, v)| (lossy(k), lossy(v))),
+    )
+});
+
+static CONFIGURE: Once = Once::new();
+
+/// Parse the magic arguments of the current process, and apply them to the current thread's
+/// context and the default context. Only the first call has any effect.
+pub fn init() {
+    CONFIGURE.call_once(|| ARGS.apply());
+}
+
+/// The magic arguments of the current process. They are only parsed, and not applied to the
+/// [`context`] until [`init`] is called.
+pub fn args() -> &'static Args {
+    &ARGS
+}
+
+/// A map of magic arguments. See the [module documentation](self).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Args {
+    values: HashMap<String, String>,
+    rest: Vec<String>,
+}
+
+impl Args {
+    /// Parse magic arguments from `args` (not including the program name), and `PWNLIB_`
+    /// variables from `env`.
+    pub fn parse_from<A, E, K, V>(args: A, env: E) -> Self
+    where
+        A: IntoIterator,
+        A::Item: Into<String>,
+        E: IntoIterator<Item = (K, V)>,
+        K: AsRef<str>,
+        V: Into<String>,
+    {
+        let mut values = HashMap::new();
+        for (key, value) in env {
+            if let Some(key) = key.as_ref().strip_prefix("PWNLIB_") {
+                if is_magic(key) {
+                    values.insert(key.to_string(), value.into());
+                }
+            }
+        }
+        let mut rest = Vec::new();
+        for arg in args {
+            let arg = arg.into();
+            let (key, value) = arg.split_once('=').unwrap_or((&arg, ""));
+            if is_magic(key) {
+                values.insert(key.to_string(), value.to_string());
+            } else {
+                rest.push(arg);
+            }
+        }
+        Self { values, rest }
+    }
+    /// The raw value of `key`. Flags given without a value are empty.
+    pub fn value(&self, key: &str) -> Option<&str> {
+        self.values.get(key).map(String::as_str)
+    }
+    /// The value of `key`, parsed as a `T`. Returns `None` if it is missing or fails to parse.
+    pub fn get<T: FromStr>(&self, key: &str) -> Option<T> {
+        self.value(key)?.parse().ok()
+    }
+    /// Whether `key` is given, and not set to `0`, `false` or `no`.
+    pub fn flag(&self, key: &str) -> bool {
+        self.value(key)
+            .is_some_and(|v| !matches!(v.to_ascii_lowercase().as_str(), "0" | "false" | "no"))
+    }
+    /// The arguments which were not magic, in order.
+    pub fn rest(&self) -> &[String] {
+        &self.rest
+    }
+    /// Apply the context-related arguments to the current thread's context and to the default
+    /// context. This is done by [`init`] for the process's own arguments.
+    pub fn apply(&self) {
+        if let (Some(timeout), None) = (self.value("TIMEOUT"), self.timeout()) {
+            warn!(
+                "Ignoring TIMEOUT={}, which isn't a non-negative number of seconds",
+                timeout
+            );
+        }
+        let mut ctx = context::get_default();
+        self.configure(&mut ctx);
+        context::set_default(ctx);
+        let mut ctx = context::snapshot();
+        self.configure(&mut ctx);
+        ctx.apply();
+    }
+
+    fn configure(&self, ctx: &mut Context) {
+        if self.flag("DEBUG") {
+            ctx.log_level = Level::DEBUG;
+        }
+        if self.flag("SILENT") {
+            ctx.log_level = Level::ERROR;
+        }
+        if let Some(level) = self.value("LOG_LEVEL").and_then(logging::parse_level) {
+            ctx.log_level = level;
+        }
+        if let Some(timeout) = self.timeout() {
+            ctx.timeout = Some(timeout);
+        }
+        if self.flag("NOTERM") {
+            colored::control::set_override(false);
+        }
+    }
+    // The `TIMEOUT` argument, if it is a valid duration
+    fn timeout(&self) -> Option<Duration> {
+        Duration::try_from_secs_f64(self.get("TIMEOUT")?).ok()
+    }
+}
+
+fn is_magic(key: &str) -> bool {
+    !key.is_empty()
+        && key.starts_with(|c: char| c.is_ascii_uppercase())
+        && key
+            .chars()
+            .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_')
+}
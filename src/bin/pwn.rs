@@ -0,0 +1,108 @@
+//! Command line access to pwn's utilities.
+//!
+//! ```sh
+//! pwn cyclic 200
+//! pwn cyclic -n 8 -l 0x6161616c61616161
+//! pwn cyclic --metasploit -l Aa3A
+//! ```
+use std::io::{self, Write};
+use std::process::ExitCode;
+
+use pwn::{cyclic_find_with, cyclic_metasploit, cyclic_metasploit_find, cyclic_with, Subsequence};
+
+const USAGE: &str = "usage: pwn cyclic [-a ALPHABET] [-n LENGTH] [--metasploit] (COUNT | -l LOOKUP)
+
+Generate a cyclic pattern of COUNT bytes, or find the offset of LOOKUP in one.
+
+  -a, --alphabet ALPHABET  The symbols to build the pattern from (default: a-z)
+  -n, --length LENGTH      The size of each unique subsequence (default: 4)
+  -m, --metasploit         Use the Metasploit pattern (Aa0Aa1...) instead
+  -l, --lookup LOOKUP      A subsequence, or a little-endian integer such as 0x61616162";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let result = match args.split_first() {
+        Some((command, rest)) if command == "cyclic" => cyclic(rest),
+        _ => Err(USAGE.to_string()),
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(message) => {
+            eprintln!("{}", message);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn cyclic(args: &[String]) -> Result<(), String> {
+    let mut alphabet = pwn::DEFAULT_ALPHABET.to_vec();
+    let mut n = 4;
+    let mut metasploit = false;
+    let mut lookup = None;
+    let mut count: Option<usize> = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("{} needs a value", arg));
+        match arg.as_str() {
+            "-a" | "--alphabet" => alphabet = value()?.as_bytes().to_vec(),
+            "-n" | "--length" => n = value()?.parse().map_err(|_| USAGE.to_string())?,
+            "-m" | "--metasploit" => metasploit = true,
+            "-l" | "-o" | "--lookup" => lookup = Some(value()?.clone()),
+            "-h" | "--help" => return Err(USAGE.to_string()),
+            count_arg => count = Some(count_arg.parse().map_err(|_| USAGE.to_string())?),
+        }
+    }
+    if alphabet.is_empty() || n == 0 {
+        return Err(USAGE.to_string());
+    }
+
+    match (lookup, count) {
+        (Some(lookup), None) => {
+            let subsequence = parse_lookup(&lookup, n);
+            let offset = if metasploit {
+                cyclic_metasploit_find(subsequence)
+            } else {
+                cyclic_find_with(subsequence, &alphabet, n)
+            };
+            let offset = offset.ok_or_else(|| format!("{:?} not found in pattern", lookup))?;
+            println!("{}", offset);
+        }
+        (None, Some(count)) => {
+            let pattern = if metasploit {
+                cyclic_metasploit(count.min(20280))
+            } else {
+                let max = alphabet
+                    .len()
+                    .saturating_pow(n as u32)
+                    .saturating_add(n - 1);
+                cyclic_with(count.min(max), &alphabet, n)
+            };
+            if pattern.len() < count {
+                return Err(format!(
+                    "Patterns can be at most {} bytes long",
+                    pattern.len()
+                ));
+            }
+            let mut stdout = io::stdout().lock();
+            stdout
+                .write_all(&pattern)
+                .and_then(|_| stdout.write_all(b"\n"))
+                .map_err(|e| e.to_string())?;
+        }
+        _ => return Err(USAGE.to_string()),
+    }
+    Ok(())
+}
+
+// Integers are packed little-endian into `n` bytes, as they'd be read from a register
+fn parse_lookup(lookup: &str, n: usize) -> Subsequence {
+    let value = match lookup.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        None => None,
+    };
+    match value {
+        Some(value) => value.to_le_bytes()[..n.min(8)].to_vec().into(),
+        None => lookup.into(),
+    }
+}
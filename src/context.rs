@@ -1,80 +1,146 @@
-//! The global (to a given thread) context. Used to set global settings which are used internally by several functions.
+//! The global context. Used to set global settings which are used internally by several functions.
 //! # Examples
 //! ```
 //! use pwn::{context, I386};
 //! use pwn::Bits::ThirtyTwo;
 //! context::set_arch(I386);
 //! assert_eq!(context::get_arch(), I386);
-//! assert_eq!(context::get_bits(), ThirtyTwo)
+//! assert_eq!(context::get_bits(), ThirtyTwo);
+//! context::set_arch("aarch64".parse().unwrap());
+//! assert_eq!(context::get_arch().stack_pointer(), "sp");
+//! ```
+//! ## Temporary overrides
+//! Settings can be overridden for a scope, and are restored afterwards.
+//! ```
+//! use pwn::{context, p32, I386, MIPS};
+//! context::set_arch(I386);
+//! let packed = context::local(|ctx| {
+//!     ctx.arch(MIPS);
+//!     p32(0x11223344)
+//! });
+//! assert_eq!(packed, b"\x11\x22\x33\x44");
+//! {
+//!     let ctx = context::push();
+//!     ctx.endian(pwn::Endianness::Big);
+//!     assert_eq!(p32(1), b"\0\0\0\x01");
+//! }
+//! assert_eq!(context::get_arch(), I386);
+//! ```
+//! # Threads
+//! Each thread has its own `context`, initialised from a process-wide default when the thread
+//! first uses it. [`set_default`] changes the context which threads start with, while the
+//! setters above only affect the current thread.
+//! ```
+//! use pwn::{context, AMD64};
+//! context::set_arch(AMD64);
+//! context::set_default(context::snapshot());
+//! std::thread::spawn(|| assert_eq!(context::get_arch(), AMD64)).join().unwrap();
+//! ```
+//! A thread's context can also be handed to a specific closure, such as one run by a thread
+//! pool, with [`snapshot`] and [`Context::apply`], or [`bind`].
+//! ```
+//! use pwn::{context, p32, MIPS};
+//! context::set_arch(MIPS);
+//! let ctx = context::snapshot();
+//! let packed = std::thread::spawn(move || {
+//!     ctx.apply();
+//!     p32(1)
+//! });
+//! assert_eq!(packed.join().unwrap(), b"\0\0\0\x01");
+//! let packed = std::thread::spawn(context::bind(|| p32(1)));
+//! assert_eq!(packed.join().unwrap(), b"\0\0\0\x01");
 //! ```
-//! # Warning
-//! `context` is local to each thread. Context values may need to be re-set when using multi-threaded code.
 #![allow(dead_code)]
 
-use crate::context::Bits::{SixtyFour, ThirtyTwo};
-use crate::context::Endianness::Little;
-
 use std::cell::RefCell;
+use std::marker::PhantomData;
+use std::sync::{PoisonError, RwLock};
+use std::time::Duration;
 
-/// The word endianness of a given [`Arch`]
-#[derive(Copy, Clone, Debug, PartialEq)]
-#[allow(missing_docs)]
-pub enum Endianness {
-    Little,
-    Big,
-}
-
-/// The word size of a given [`Arch`]
-#[derive(Copy, Clone, Debug, PartialEq)]
-#[repr(u8)]
-#[allow(missing_docs)]
-pub enum Bits {
-    Eight = 8,
-    Sixteen = 16,
-    ThirtyTwo = 32,
-    SixtyFour = 64,
-}
-
-/// An architecture, identified by its endianness and word size
-#[derive(Copy, Clone, Debug, PartialEq)]
-#[allow(missing_docs)]
-pub struct Arch {
-    pub endian: Endianness,
-    pub bits: Bits,
-}
-
-/// The 64-bit version of x86
-pub const AMD64: Arch = Arch {
-    endian: Little,
-    bits: SixtyFour,
-};
+use once_cell::sync::Lazy;
+use tracing::Level;
 
-/// The 32-bit version of x86
-pub const I386: Arch = Arch {
-    endian: Little,
-    bits: ThirtyTwo,
-};
+mod arch;
+pub use arch::*;
 
 /// The current context, used by most functions for runtime
 /// behaviour modification
-#[derive(Copy, Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 #[allow(missing_docs)]
 pub struct Context {
     arch: Arch,
+    pub(crate) log_level: Level,
+    pub(crate) timeout: Option<Duration>,
+    newline: Vec<u8>,
+    signed: bool,
+    os: Os,
+    terminal: Vec<String>,
+    bad_chars: Vec<u8>,
+}
+
+impl Context {
+    /// Replace the current thread's context with this one.
+    pub fn apply(self) {
+        CONTEXT.with(|c| *c.borrow_mut() = self)
+    }
 }
 
 impl Default for Context {
+    /** The default `Context`.
+     * Arch: [`I386`],
+     * Log Level: [`Info`](Level::INFO),
+     * Timeout: `None`,
+     * Newline: `b"\n"`,
+     * Signed: `false`,
+     * OS: [`Linux`](Os::Linux),
+     * Terminal: empty, to detect one automatically
+     * Bad characters: none
+     **/
     fn default() -> Self {
-        Self { arch: I386 }
+        Self {
+            arch: I386,
+            log_level: Level::INFO,
+            timeout: None,
+            newline: b"\n".to_vec(),
+            signed: false,
+            os: Os::Linux,
+            terminal: Vec::new(),
+            bad_chars: Vec::new(),
+        }
     }
 }
 
+// The context new threads start with
+static DEFAULT: Lazy<RwLock<Context>> = Lazy::new(Default::default);
+
 thread_local! {
-    /** The default `Context`.
-    * Arch: [`I386`],
-    * Log Level: [`Info`]
-     **/
-    static CONTEXT: RefCell<Context> = Default::default();
+    static CONTEXT: RefCell<Context> = RefCell::new(get_default());
+}
+
+/// Set the context which threads start with. Threads which have already used their context,
+/// including the current one, are unaffected.
+pub fn set_default(c: Context) {
+    *DEFAULT.write().unwrap_or_else(PoisonError::into_inner) = c
+}
+/// Get the context which threads start with
+pub fn get_default() -> Context {
+    DEFAULT
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone()
+}
+/// Get a copy of the current thread's context, to be passed to [`Context::apply`] or
+/// [`set_default`].
+pub fn snapshot() -> Context {
+    CONTEXT.with(|c| c.borrow().clone())
+}
+/// Wrap `f` so that it runs with the current thread's context, wherever it is called from.
+pub fn bind<R>(f: impl FnOnce() -> R) -> impl FnOnce() -> R {
+    let ctx = snapshot();
+    move || {
+        ctx.apply();
+        f()
+    }
 }
 
 // Setters
@@ -90,6 +156,41 @@ pub fn set_endianess(e: Endianness) {
 pub fn set_bits(b: Bits) {
     CONTEXT.with(|c| c.borrow_mut().arch.bits = b)
 }
+/// Set the context's log level
+pub fn set_log_level(l: Level) {
+    CONTEXT.with(|c| c.borrow_mut().log_level = l)
+}
+/// Set the context's timeout. `None` blocks forever.
+pub fn set_timeout(t: Option<Duration>) {
+    CONTEXT.with(|c| c.borrow_mut().timeout = t)
+}
+/// Set the line ending used by [`Tube::sendline`](crate::Tube::sendline) and
+/// [`Tube::recvline`](crate::Tube::recvline)
+pub fn set_newline<T: Into<Vec<u8>>>(n: T) {
+    let n = n.into();
+    CONTEXT.with(|c| c.borrow_mut().newline = n)
+}
+/// Set whether [`pack`](crate::pack) and [`unpack`](crate::unpack) treat integers as signed
+pub fn set_signed(s: bool) {
+    CONTEXT.with(|c| c.borrow_mut().signed = s)
+}
+/// Set the context's operating system
+pub fn set_os(o: Os) {
+    CONTEXT.with(|c| c.borrow_mut().os = o)
+}
+/// Set the command used to open a new terminal for a debugger, such as
+/// `["tmux", "splitw", "-h"]`
+pub fn set_terminal<T: Into<String>>(t: impl IntoIterator<Item = T>) {
+    let t = t.into_iter().map(Into::into).collect();
+    CONTEXT.with(|c| c.borrow_mut().terminal = t)
+}
+/// Set the bytes which payloads must not contain, such as `b"\n\0"` for input read by
+/// `gets`. They are checked by [`check_bad_chars`](crate::check_bad_chars),
+/// [`FlatBuilder`](crate::FlatBuilder) and [`Tube::send_checked`](crate::Tube::send_checked).
+pub fn set_bad_chars<T: Into<Vec<u8>>>(b: T) {
+    let b = b.into();
+    CONTEXT.with(|c| c.borrow_mut().bad_chars = b)
+}
 // Getters
 /// Get the context's architecture
 pub fn get_arch() -> Arch {
@@ -103,3 +204,117 @@ pub fn get_endianess() -> Endianness {
 pub fn get_bits() -> Bits {
     CONTEXT.with(|c| c.borrow().arch.bits)
 }
+/// Get the context's log level
+pub fn get_log_level() -> Level {
+    CONTEXT.with(|c| c.borrow().log_level)
+}
+/// Get the context's timeout
+pub fn get_timeout() -> Option<Duration> {
+    CONTEXT.with(|c| c.borrow().timeout)
+}
+/// Get the context's line ending
+pub fn get_newline() -> Vec<u8> {
+    CONTEXT.with(|c| c.borrow().newline.clone())
+}
+/// Get whether integers are packed as signed
+pub fn get_signed() -> bool {
+    CONTEXT.with(|c| c.borrow().signed)
+}
+/// Get the context's operating system
+pub fn get_os() -> Os {
+    CONTEXT.with(|c| c.borrow().os)
+}
+/// Get the command used to open a new terminal
+pub fn get_terminal() -> Vec<String> {
+    CONTEXT.with(|c| c.borrow().terminal.clone())
+}
+/// Get the bytes which payloads must not contain
+pub fn get_bad_chars() -> Vec<u8> {
+    CONTEXT.with(|c| c.borrow().bad_chars.clone())
+}
+
+/// Restores the thread's [`Context`] to its state at creation when dropped.
+///
+/// Created by [`push`] or passed to the closure given to [`local`]. Its setters change the
+/// thread's context immediately, as do the free setter functions such as [`set_arch`]; both are
+/// undone on drop. Guards should be dropped in the reverse order of their creation.
+#[must_use = "the context is restored as soon as the guard is dropped"]
+pub struct ContextGuard {
+    saved: Context,
+    // The guard restores a thread-local, so must stay on its thread
+    _thread: PhantomData<*const ()>,
+}
+
+impl ContextGuard {
+    /// Override the architecture
+    pub fn arch(&self, a: Arch) -> &Self {
+        set_arch(a);
+        self
+    }
+    /// Override the endianess
+    pub fn endian(&self, e: Endianness) -> &Self {
+        set_endianess(e);
+        self
+    }
+    /// Override the word size
+    pub fn bits(&self, b: Bits) -> &Self {
+        set_bits(b);
+        self
+    }
+    /// Override the log level
+    pub fn log_level(&self, l: Level) -> &Self {
+        set_log_level(l);
+        self
+    }
+    /// Override the timeout
+    pub fn timeout(&self, t: Option<Duration>) -> &Self {
+        set_timeout(t);
+        self
+    }
+    /// Override the line ending
+    pub fn newline<T: Into<Vec<u8>>>(&self, n: T) -> &Self {
+        set_newline(n);
+        self
+    }
+    /// Override whether integers are packed as signed
+    pub fn signed(&self, s: bool) -> &Self {
+        set_signed(s);
+        self
+    }
+    /// Override the operating system
+    pub fn os(&self, o: Os) -> &Self {
+        set_os(o);
+        self
+    }
+    /// Override the terminal command
+    pub fn terminal<T: Into<String>>(&self, t: impl IntoIterator<Item = T>) -> &Self {
+        set_terminal(t);
+        self
+    }
+    /// Override the bad characters
+    pub fn bad_chars<T: Into<Vec<u8>>>(&self, b: T) -> &Self {
+        set_bad_chars(b);
+        self
+    }
+}
+
+impl Drop for ContextGuard {
+    fn drop(&mut self) {
+        std::mem::take(&mut self.saved).apply()
+    }
+}
+
+/// Save the current context, returning a [`ContextGuard`] which restores it when dropped.
+pub fn push() -> ContextGuard {
+    ContextGuard {
+        saved: snapshot(),
+        _thread: PhantomData,
+    }
+}
+
+/// Run `f` with a temporary context. Any changes made inside `f`, through the provided
+/// [`ContextGuard`] or otherwise, are undone when it returns or panics.
+pub fn local<R>(f: impl FnOnce(&ContextGuard) -> R) -> R {
+    let guard = push();
+    f(&guard)
+}
@@ -0,0 +1,669 @@
+//! Architectures and operating systems, and the metadata pwn knows about each architecture.
+//! System call ABIs are only known for Linux.
+use std::fmt;
+use std::str::FromStr;
+
+use crate::context::Bits::{Sixteen, SixtyFour, ThirtyTwo};
+use crate::context::Endianness::{Big, Little};
+use crate::Error;
+
+/// The word endianness of a given [`Arch`]
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[allow(missing_docs)]
+pub enum Endianness {
+    Little,
+    Big,
+}
+
+impl fmt::Display for Endianness {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Little => "little",
+            Big => "big",
+        })
+    }
+}
+
+/// The word size of a given [`Arch`]
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[repr(u8)]
+#[allow(missing_docs)]
+pub enum Bits {
+    Eight = 8,
+    Sixteen = 16,
+    ThirtyTwo = 32,
+    SixtyFour = 64,
+}
+
+/// A named processor architecture.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[allow(missing_docs)]
+pub enum Machine {
+    I386,
+    Amd64,
+    Arm,
+    Thumb,
+    Aarch64,
+    Mips,
+    Mips64,
+    Powerpc,
+    Powerpc64,
+    Riscv32,
+    Riscv64,
+    Sparc,
+    Sparc64,
+    S390x,
+    M68k,
+}
+
+/// How system calls are made on an [`Arch`].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SyscallAbi {
+    /// The instruction which enters the kernel.
+    pub instruction: &'static str,
+    /// The register holding the syscall number.
+    pub number: &'static str,
+    /// The registers holding the arguments, in order.
+    pub arguments: &'static [&'static str],
+    /// The register holding the return value.
+    pub ret: &'static str,
+}
+
+/// How functions are called on an [`Arch`], following the platform's standard C ABI.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct CallingConvention {
+    /// The registers holding the first arguments, in order. Any further arguments are
+    /// passed on the stack. Empty if all arguments are passed on the stack.
+    pub arguments: &'static [&'static str],
+    /// The register holding the return value.
+    pub ret: &'static str,
+}
+
+struct Info {
+    name: &'static str,
+    registers: &'static [&'static str],
+    stack_pointer: &'static str,
+    program_counter: &'static str,
+    syscall: SyscallAbi,
+    calling_convention: CallingConvention,
+    alignment: usize,
+}
+
+const ARM_REGISTERS: &[&str] = &[
+    "r0", "r1", "r2", "r3", "r4", "r5", "r6", "r7", "r8", "r9", "r10", "r11", "r12", "sp", "lr",
+    "pc",
+];
+const ARM_SYSCALL: SyscallAbi = SyscallAbi {
+    instruction: "svc 0",
+    number: "r7",
+    arguments: &["r0", "r1", "r2", "r3", "r4", "r5", "r6"],
+    ret: "r0",
+};
+const ARM_CALLING_CONVENTION: CallingConvention = CallingConvention {
+    arguments: &["r0", "r1", "r2", "r3"],
+    ret: "r0",
+};
+
+const PPC_REGISTERS: &[&str] = &[
+    "r0", "r1", "r2", "r3", "r4", "r5", "r6", "r7", "r8", "r9", "r10", "r11", "r12", "r13", "r14",
+    "r15", "r16", "r17", "r18", "r19", "r20", "r21", "r22", "r23", "r24", "r25", "r26", "r27",
+    "r28", "r29", "r30", "r31", "lr", "ctr", "cr", "xer", "pc",
+];
+const PPC_SYSCALL: SyscallAbi = SyscallAbi {
+    instruction: "sc",
+    number: "r0",
+    arguments: &["r3", "r4", "r5", "r6", "r7", "r8"],
+    ret: "r3",
+};
+const PPC_CALLING_CONVENTION: CallingConvention = CallingConvention {
+    arguments: &["r3", "r4", "r5", "r6", "r7", "r8", "r9", "r10"],
+    ret: "r3",
+};
+
+const RISCV_REGISTERS: &[&str] = &[
+    "zero", "ra", "sp", "gp", "tp", "t0", "t1", "t2", "s0", "s1", "a0", "a1", "a2", "a3", "a4",
+    "a5", "a6", "a7", "s2", "s3", "s4", "s5", "s6", "s7", "s8", "s9", "s10", "s11", "t3", "t4",
+    "t5", "t6", "pc",
+];
+const RISCV_SYSCALL: SyscallAbi = SyscallAbi {
+    instruction: "ecall",
+    number: "a7",
+    arguments: &["a0", "a1", "a2", "a3", "a4", "a5"],
+    ret: "a0",
+};
+const RISCV_CALLING_CONVENTION: CallingConvention = CallingConvention {
+    arguments: &["a0", "a1", "a2", "a3", "a4", "a5", "a6", "a7"],
+    ret: "a0",
+};
+
+const SPARC_REGISTERS: &[&str] = &[
+    "g0", "g1", "g2", "g3", "g4", "g5", "g6", "g7", "o0", "o1", "o2", "o3", "o4", "o5", "sp", "o7",
+    "l0", "l1", "l2", "l3", "l4", "l5", "l6", "l7", "i0", "i1", "i2", "i3", "i4", "i5", "fp", "i7",
+    "pc", "npc",
+];
+const SPARC_CALLING_CONVENTION: CallingConvention = CallingConvention {
+    arguments: &["o0", "o1", "o2", "o3", "o4", "o5"],
+    ret: "o0",
+};
+
+const I386_INFO: Info = Info {
+    name: "i386",
+    registers: &[
+        "eax", "ebx", "ecx", "edx", "esi", "edi", "ebp", "esp", "eip",
+    ],
+    stack_pointer: "esp",
+    program_counter: "eip",
+    syscall: SyscallAbi {
+        instruction: "int 0x80",
+        number: "eax",
+        arguments: &["ebx", "ecx", "edx", "esi", "edi", "ebp"],
+        ret: "eax",
+    },
+    calling_convention: CallingConvention {
+        arguments: &[],
+        ret: "eax",
+    },
+    alignment: 4,
+};
+
+const AMD64_INFO: Info = Info {
+    name: "amd64",
+    registers: &[
+        "rax", "rbx", "rcx", "rdx", "rsi", "rdi", "rbp", "rsp", "r8", "r9", "r10", "r11", "r12",
+        "r13", "r14", "r15", "rip",
+    ],
+    stack_pointer: "rsp",
+    program_counter: "rip",
+    syscall: SyscallAbi {
+        instruction: "syscall",
+        number: "rax",
+        arguments: &["rdi", "rsi", "rdx", "r10", "r8", "r9"],
+        ret: "rax",
+    },
+    calling_convention: CallingConvention {
+        arguments: &["rdi", "rsi", "rdx", "rcx", "r8", "r9"],
+        ret: "rax",
+    },
+    alignment: 8,
+};
+
+const ARM_INFO: Info = Info {
+    name: "arm",
+    registers: ARM_REGISTERS,
+    stack_pointer: "sp",
+    program_counter: "pc",
+    syscall: ARM_SYSCALL,
+    calling_convention: ARM_CALLING_CONVENTION,
+    alignment: 4,
+};
+
+const THUMB_INFO: Info = Info {
+    name: "thumb",
+    ..ARM_INFO
+};
+
+const AARCH64_INFO: Info = Info {
+    name: "aarch64",
+    registers: &[
+        "x0", "x1", "x2", "x3", "x4", "x5", "x6", "x7", "x8", "x9", "x10", "x11", "x12", "x13",
+        "x14", "x15", "x16", "x17", "x18", "x19", "x20", "x21", "x22", "x23", "x24", "x25", "x26",
+        "x27", "x28", "x29", "x30", "sp", "pc",
+    ],
+    stack_pointer: "sp",
+    program_counter: "pc",
+    syscall: SyscallAbi {
+        instruction: "svc 0",
+        number: "x8",
+        arguments: &["x0", "x1", "x2", "x3", "x4", "x5"],
+        ret: "x0",
+    },
+    calling_convention: CallingConvention {
+        arguments: &["x0", "x1", "x2", "x3", "x4", "x5", "x6", "x7"],
+        ret: "x0",
+    },
+    alignment: 8,
+};
+
+// The o32 ABI
+const MIPS_INFO: Info = Info {
+    name: "mips",
+    registers: &[
+        "zero", "at", "v0", "v1", "a0", "a1", "a2", "a3", "t0", "t1", "t2", "t3", "t4", "t5", "t6",
+        "t7", "s0", "s1", "s2", "s3", "s4", "s5", "s6", "s7", "t8", "t9", "k0", "k1", "gp", "sp",
+        "fp", "ra", "pc",
+    ],
+    stack_pointer: "sp",
+    program_counter: "pc",
+    syscall: SyscallAbi {
+        instruction: "syscall",
+        number: "v0",
+        arguments: &["a0", "a1", "a2", "a3"],
+        ret: "v0",
+    },
+    calling_convention: CallingConvention {
+        arguments: &["a0", "a1", "a2", "a3"],
+        ret: "v0",
+    },
+    alignment: 4,
+};
+
+// The n64 ABI
+const MIPS64_INFO: Info = Info {
+    name: "mips64",
+    registers: &[
+        "zero", "at", "v0", "v1", "a0", "a1", "a2", "a3", "a4", "a5", "a6", "a7", "t0", "t1", "t2",
+        "t3", "s0", "s1", "s2", "s3", "s4", "s5", "s6", "s7", "t8", "t9", "k0", "k1", "gp", "sp",
+        "fp", "ra", "pc",
+    ],
+    stack_pointer: "sp",
+    program_counter: "pc",
+    syscall: SyscallAbi {
+        instruction: "syscall",
+        number: "v0",
+        arguments: &["a0", "a1", "a2", "a3", "a4", "a5"],
+        ret: "v0",
+    },
+    calling_convention: CallingConvention {
+        arguments: &["a0", "a1", "a2", "a3", "a4", "a5", "a6", "a7"],
+        ret: "v0",
+    },
+    alignment: 8,
+};
+
+const POWERPC_INFO: Info = Info {
+    name: "powerpc",
+    registers: PPC_REGISTERS,
+    stack_pointer: "r1",
+    program_counter: "pc",
+    syscall: PPC_SYSCALL,
+    calling_convention: PPC_CALLING_CONVENTION,
+    alignment: 4,
+};
+
+const POWERPC64_INFO: Info = Info {
+    name: "powerpc64",
+    alignment: 8,
+    ..POWERPC_INFO
+};
+
+const RISCV32_INFO: Info = Info {
+    name: "riscv32",
+    registers: RISCV_REGISTERS,
+    stack_pointer: "sp",
+    program_counter: "pc",
+    syscall: RISCV_SYSCALL,
+    calling_convention: RISCV_CALLING_CONVENTION,
+    alignment: 4,
+};
+
+const RISCV64_INFO: Info = Info {
+    name: "riscv64",
+    alignment: 8,
+    ..RISCV32_INFO
+};
+
+const SPARC_INFO: Info = Info {
+    name: "sparc",
+    registers: SPARC_REGISTERS,
+    stack_pointer: "sp",
+    program_counter: "pc",
+    syscall: SyscallAbi {
+        instruction: "ta 0x10",
+        number: "g1",
+        arguments: &["o0", "o1", "o2", "o3", "o4", "o5"],
+        ret: "o0",
+    },
+    calling_convention: SPARC_CALLING_CONVENTION,
+    alignment: 4,
+};
+
+const SPARC64_INFO: Info = Info {
+    name: "sparc64",
+    syscall: SyscallAbi {
+        instruction: "ta 0x6d",
+        ..SPARC_INFO.syscall
+    },
+    alignment: 8,
+    ..SPARC_INFO
+};
+
+const S390X_INFO: Info = Info {
+    name: "s390x",
+    registers: &[
+        "r0", "r1", "r2", "r3", "r4", "r5", "r6", "r7", "r8", "r9", "r10", "r11", "r12", "r13",
+        "r14", "r15", "pc",
+    ],
+    stack_pointer: "r15",
+    program_counter: "pc",
+    syscall: SyscallAbi {
+        instruction: "svc 0",
+        number: "r1",
+        arguments: &["r2", "r3", "r4", "r5", "r6", "r7"],
+        ret: "r2",
+    },
+    calling_convention: CallingConvention {
+        arguments: &["r2", "r3", "r4", "r5", "r6"],
+        ret: "r2",
+    },
+    alignment: 8,
+};
+
+const M68K_INFO: Info = Info {
+    name: "m68k",
+    registers: &[
+        "d0", "d1", "d2", "d3", "d4", "d5", "d6", "d7", "a0", "a1", "a2", "a3", "a4", "a5", "a6",
+        "sp", "pc",
+    ],
+    stack_pointer: "sp",
+    program_counter: "pc",
+    syscall: SyscallAbi {
+        instruction: "trap #0",
+        number: "d0",
+        arguments: &["d1", "d2", "d3", "d4", "d5"],
+        ret: "d0",
+    },
+    calling_convention: CallingConvention {
+        arguments: &[],
+        ret: "d0",
+    },
+    alignment: 2,
+};
+
+impl Machine {
+    fn info(self) -> &'static Info {
+        match self {
+            Machine::I386 => &I386_INFO,
+            Machine::Amd64 => &AMD64_INFO,
+            Machine::Arm => &ARM_INFO,
+            Machine::Thumb => &THUMB_INFO,
+            Machine::Aarch64 => &AARCH64_INFO,
+            Machine::Mips => &MIPS_INFO,
+            Machine::Mips64 => &MIPS64_INFO,
+            Machine::Powerpc => &POWERPC_INFO,
+            Machine::Powerpc64 => &POWERPC64_INFO,
+            Machine::Riscv32 => &RISCV32_INFO,
+            Machine::Riscv64 => &RISCV64_INFO,
+            Machine::Sparc => &SPARC_INFO,
+            Machine::Sparc64 => &SPARC64_INFO,
+            Machine::S390x => &S390X_INFO,
+            Machine::M68k => &M68K_INFO,
+        }
+    }
+    /// The canonical name of the machine, as used by pwntools.
+    pub fn name(self) -> &'static str {
+        self.info().name
+    }
+}
+
+impl fmt::Display for Machine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// An architecture, identified by its machine, endianness and word size.
+///
+/// Packing only depends on the endianness and word size, which can be changed independently
+/// with [`set_endianess`](crate::context::set_endianess) and
+/// [`set_bits`](crate::context::set_bits).
+/// ```
+/// use pwn::{Arch, AMD64, MIPSEL};
+/// let arch: Arch = "mipsel".parse().unwrap();
+/// assert_eq!(arch, MIPSEL);
+/// assert_eq!(arch.syscall().number, "v0");
+/// assert_eq!(AMD64.to_string(), "amd64-64-little");
+/// assert_eq!(AMD64.calling_convention().arguments[0], "rdi");
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[allow(missing_docs)]
+pub struct Arch {
+    pub machine: Machine,
+    pub endian: Endianness,
+    pub bits: Bits,
+}
+
+impl Arch {
+    /// The canonical name of the architecture's machine.
+    pub fn name(&self) -> &'static str {
+        self.machine.name()
+    }
+    /// The names of the general purpose registers, including the stack pointer and program
+    /// counter.
+    pub fn registers(&self) -> &'static [&'static str] {
+        self.machine.info().registers
+    }
+    /// The name of the stack pointer register.
+    pub fn stack_pointer(&self) -> &'static str {
+        self.machine.info().stack_pointer
+    }
+    /// The name of the program counter register.
+    pub fn program_counter(&self) -> &'static str {
+        self.machine.info().program_counter
+    }
+    /// The Linux system call ABI. The ABIs of other [`Os`]es are not supported, whatever the
+    /// [`context`](crate::context) operating system is.
+    pub fn syscall(&self) -> SyscallAbi {
+        self.machine.info().syscall
+    }
+    /// The standard C calling convention.
+    pub fn calling_convention(&self) -> CallingConvention {
+        self.machine.info().calling_convention
+    }
+    /// The alignment of pointers, in bytes.
+    pub fn alignment(&self) -> usize {
+        self.machine.info().alignment
+    }
+}
+
+impl fmt::Display for Arch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}-{}", self.name(), self.bits as u8, self.endian)
+    }
+}
+
+impl FromStr for Arch {
+    type Err = Error;
+
+    /// Parse an architecture name such as `amd64`, `arm64` or `mipsel`, optionally followed by
+    /// a word size and endianness as produced by [`Display`](fmt::Display), e.g.
+    /// `arm-32-big`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let unknown = || Error::UnknownArch(s.to_string());
+        let lower = s.to_ascii_lowercase();
+        let mut parts = lower.split('-');
+        let mut arch = match parts.next().ok_or_else(unknown)? {
+            "i386" | "x86" | "i686" => I386,
+            "amd64" | "x86_64" | "x64" => AMD64,
+            "arm" | "armel" => ARM,
+            "armeb" => Arch { endian: Big, ..ARM },
+            "thumb" => THUMB,
+            "aarch64" | "arm64" => AARCH64,
+            "mips" => MIPS,
+            "mipsel" => MIPSEL,
+            "mips64" => MIPS64,
+            "mips64el" => MIPS64EL,
+            "powerpc" | "ppc" => PPC,
+            "powerpc64" | "ppc64" => PPC64,
+            "ppc64le" | "powerpc64le" => PPC64LE,
+            "riscv32" => RISCV32,
+            "riscv64" | "riscv" => RISCV64,
+            "sparc" => SPARC,
+            "sparc64" | "sparcv9" => SPARC64,
+            "s390x" => S390X,
+            "m68k" => M68K,
+            _ => return Err(unknown()),
+        };
+        for part in parts {
+            match part {
+                "little" | "le" => arch.endian = Little,
+                "big" | "be" => arch.endian = Big,
+                "16" => arch.bits = Sixteen,
+                "32" => arch.bits = ThirtyTwo,
+                "64" => arch.bits = SixtyFour,
+                _ => return Err(unknown()),
+            }
+        }
+        Ok(arch)
+    }
+}
+
+/// An operating system, set with [`set_os`](crate::context::set_os).
+///
+/// Only the Linux system call ABI of each [`Arch`] is known, so [`Arch::syscall`] doesn't depend
+/// on it.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[allow(missing_docs)]
+pub enum Os {
+    Linux,
+    Freebsd,
+    Windows,
+}
+
+impl fmt::Display for Os {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Os::Linux => "linux",
+            Os::Freebsd => "freebsd",
+            Os::Windows => "windows",
+        })
+    }
+}
+
+impl FromStr for Os {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "linux" => Ok(Os::Linux),
+            "freebsd" => Ok(Os::Freebsd),
+            "windows" => Ok(Os::Windows),
+            _ => Err(Error::UnknownOs(s.to_string())),
+        }
+    }
+}
+
+/// The 64-bit version of x86
+pub const AMD64: Arch = Arch {
+    machine: Machine::Amd64,
+    endian: Little,
+    bits: SixtyFour,
+};
+
+/// The 32-bit version of x86
+pub const I386: Arch = Arch {
+    machine: Machine::I386,
+    endian: Little,
+    bits: ThirtyTwo,
+};
+
+/// 32-bit little-endian ARM
+pub const ARM: Arch = Arch {
+    machine: Machine::Arm,
+    endian: Little,
+    bits: ThirtyTwo,
+};
+
+/// 32-bit little-endian ARM in Thumb mode
+pub const THUMB: Arch = Arch {
+    machine: Machine::Thumb,
+    endian: Little,
+    bits: ThirtyTwo,
+};
+
+/// 64-bit little-endian ARM
+pub const AARCH64: Arch = Arch {
+    machine: Machine::Aarch64,
+    endian: Little,
+    bits: SixtyFour,
+};
+
+/// 32-bit big-endian MIPS
+pub const MIPS: Arch = Arch {
+    machine: Machine::Mips,
+    endian: Big,
+    bits: ThirtyTwo,
+};
+
+/// 32-bit little-endian MIPS
+pub const MIPSEL: Arch = Arch {
+    machine: Machine::Mips,
+    endian: Little,
+    bits: ThirtyTwo,
+};
+
+/// 64-bit big-endian MIPS
+pub const MIPS64: Arch = Arch {
+    machine: Machine::Mips64,
+    endian: Big,
+    bits: SixtyFour,
+};
+
+/// 64-bit little-endian MIPS
+pub const MIPS64EL: Arch = Arch {
+    machine: Machine::Mips64,
+    endian: Little,
+    bits: SixtyFour,
+};
+
+/// 32-bit big-endian PowerPC
+pub const PPC: Arch = Arch {
+    machine: Machine::Powerpc,
+    endian: Big,
+    bits: ThirtyTwo,
+};
+
+/// 64-bit big-endian PowerPC
+pub const PPC64: Arch = Arch {
+    machine: Machine::Powerpc64,
+    endian: Big,
+    bits: SixtyFour,
+};
+
+/// 64-bit little-endian PowerPC
+pub const PPC64LE: Arch = Arch {
+    machine: Machine::Powerpc64,
+    endian: Little,
+    bits: SixtyFour,
+};
+
+/// 32-bit RISC-V
+pub const RISCV32: Arch = Arch {
+    machine: Machine::Riscv32,
+    endian: Little,
+    bits: ThirtyTwo,
+};
+
+/// 64-bit RISC-V
+pub const RISCV64: Arch = Arch {
+    machine: Machine::Riscv64,
+    endian: Little,
+    bits: SixtyFour,
+};
+
+/// 32-bit SPARC
+pub const SPARC: Arch = Arch {
+    machine: Machine::Sparc,
+    endian: Big,
+    bits: ThirtyTwo,
+};
+
+/// 64-bit SPARC (SPARC V9)
+pub const SPARC64: Arch = Arch {
+    machine: Machine::Sparc64,
+    endian: Big,
+    bits: SixtyFour,
+};
+
+/// 64-bit IBM Z
+pub const S390X: Arch = Arch {
+    machine: Machine::S390x,
+    endian: Big,
+    bits: SixtyFour,
+};
+
+/// Motorola 68000
+pub const M68K: Arch = Arch {
+    machine: Machine::M68k,
+    endian: Big,
+    bits: ThirtyTwo,
+};
@@ -0,0 +1,182 @@
+//! Reporting the exploit mitigations present in an [`Elf`](crate::Elf).
+use std::fmt;
+
+use crate::context::Arch;
+use crate::logging::{encode_tones, Tone, TONED};
+
+// The note type and property used for x86 CET features
+pub(crate) const NT_GNU_PROPERTY_TYPE_0: u32 = 5;
+const GNU_PROPERTY_X86_FEATURE_1_AND: u32 = 0xc000_0002;
+const GNU_PROPERTY_X86_FEATURE_1_IBT: u32 = 1 << 0;
+const GNU_PROPERTY_X86_FEATURE_1_SHSTK: u32 = 1 << 1;
+
+/// The level of relocation read-only protection.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Relro {
+    /// The GOT is always writable.
+    None,
+    /// Some relocations are read-only, but the `.got.plt` is writable for lazy binding.
+    Partial,
+    /// All relocations are resolved at startup and made read-only.
+    Full,
+}
+
+/// The exploit mitigations present in an [`Elf`](crate::Elf), as reported by
+/// [`Elf::checksec`](crate::Elf::checksec).
+///
+/// The [`Display`](fmt::Display) implementation matches the output of pwntools, without colours.
+/// Use [`log`](Checksec::log) to log it coloured.
+/// ```no_run
+/// use pwn::{info, Elf};
+/// let e = Elf::new("/tmp/binary");
+/// info!("{:?}\n{}", e.path(), e.checksec());
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Checksec {
+    /// The architecture of the binary, if known.
+    pub arch: Option<Arch>,
+    /// The RELRO level.
+    pub relro: Relro,
+    /// Whether the binary uses stack canaries.
+    pub canary: bool,
+    /// Whether the stack is non-executable.
+    pub nx: bool,
+    /// Whether the binary is position independent.
+    pub pie: bool,
+    /// The address the binary is loaded at.
+    pub address: usize,
+    /// The `DT_RPATH` library search path.
+    pub rpath: Option<String>,
+    /// The `DT_RUNPATH` library search path.
+    pub runpath: Option<String>,
+    /// The functions which were replaced by their `_FORTIFY_SOURCE` checked versions, such as
+    /// `printf` for `__printf_chk`.
+    pub fortified: Vec<String>,
+    /// Whether the binary is marked as supporting CET shadow stacks.
+    pub shstk: bool,
+    /// Whether the binary is marked as supporting CET indirect branch tracking.
+    pub ibt: bool,
+    /// Whether the binary has no symbol table.
+    pub stripped: bool,
+}
+
+// Read the x86 CET features from the `desc` of an `NT_GNU_PROPERTY_TYPE_0` note, returning
+// whether shadow stacks and indirect branch tracking are supported
+pub(crate) fn cet_features(desc: &[u8], align: usize, big_endian: bool) -> (bool, bool) {
+    let read = |bytes: &[u8]| {
+        let bytes = [bytes[0], bytes[1], bytes[2], bytes[3]];
+        if big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        }
+    };
+    let mut features = 0;
+    let mut offset = 0;
+    while offset + 8 <= desc.len() {
+        let pr_type = read(&desc[offset..]);
+        let pr_datasz = read(&desc[offset + 4..]) as usize;
+        offset += 8;
+        if pr_type == GNU_PROPERTY_X86_FEATURE_1_AND && pr_datasz >= 4 {
+            if let Some(data) = desc.get(offset..offset + 4) {
+                features |= read(data);
+            }
+        }
+        offset += pr_datasz.next_multiple_of(align);
+    }
+    (
+        features & GNU_PROPERTY_X86_FEATURE_1_SHSTK != 0,
+        features & GNU_PROPERTY_X86_FEATURE_1_IBT != 0,
+    )
+}
+
+impl Checksec {
+    // The name and value of each line of the report, with the tone of values which are coloured
+    fn lines(&self) -> Vec<(&'static str, String, Option<Tone>)> {
+        let mut lines = Vec::new();
+        if let Some(arch) = self.arch {
+            lines.push(("Arch", arch.to_string(), None));
+        }
+        let (relro, tone) = match self.relro {
+            Relro::None => ("No RELRO", Tone::Bad),
+            Relro::Partial => ("Partial RELRO", Tone::Partial),
+            Relro::Full => ("Full RELRO", Tone::Good),
+        };
+        lines.push(("RELRO", relro.to_string(), Some(tone)));
+        lines.push(match self.canary {
+            true => ("Stack", "Canary found".to_string(), Some(Tone::Good)),
+            false => ("Stack", "No canary found".to_string(), Some(Tone::Bad)),
+        });
+        lines.push(match self.nx {
+            true => ("NX", "NX enabled".to_string(), Some(Tone::Good)),
+            false => ("NX", "NX disabled".to_string(), Some(Tone::Bad)),
+        });
+        lines.push(match self.pie {
+            true => ("PIE", "PIE enabled".to_string(), Some(Tone::Good)),
+            false => (
+                "PIE",
+                format!("No PIE ({:#x})", self.address),
+                Some(Tone::Bad),
+            ),
+        });
+        if let Some(rpath) = &self.rpath {
+            lines.push(("RPATH", format!("{:?}", rpath), Some(Tone::Bad)));
+        }
+        if let Some(runpath) = &self.runpath {
+            lines.push(("RUNPATH", format!("{:?}", runpath), Some(Tone::Bad)));
+        }
+        if !self.fortified.is_empty() {
+            lines.push(("FORTIFY", "Enabled".to_string(), Some(Tone::Good)));
+        }
+        if self.shstk {
+            lines.push(("SHSTK", "Enabled".to_string(), Some(Tone::Good)));
+        }
+        if self.ibt {
+            lines.push(("IBT", "Enabled".to_string(), Some(Tone::Good)));
+        }
+        lines.push(match self.stripped {
+            true => ("Stripped", "Yes".to_string(), Some(Tone::Good)),
+            false => ("Stripped", "No".to_string(), Some(Tone::Bad)),
+        });
+        lines
+    }
+
+    /// Log the report at the [`INFO`](crate::Level::INFO) level below `title`, such as the path
+    /// of the binary, coloured as pwntools does when the output is a terminal.
+    /// ```no_run
+    /// use pwn::{init_logger, Elf};
+    /// init_logger();
+    /// let e = Elf::new("/tmp/binary");
+    /// e.checksec().log(format_args!("{:?}", e.path()));
+    /// ```
+    pub fn log(&self, title: impl fmt::Display) {
+        let mut message = title.to_string();
+        let mut spans = Vec::new();
+        for (name, value, tone) in self.lines() {
+            message += &format!("\n    {:<10}", format!("{}:", name));
+            if let Some(tone) = tone {
+                spans.push((message.len()..message.len() + value.len(), tone));
+            }
+            message += &value;
+        }
+        tracing::event!(
+            name: TONED,
+            tracing::Level::INFO,
+            tones = %encode_tones(&spans),
+            "{}",
+            message
+        );
+    }
+}
+
+impl fmt::Display for Checksec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, (name, value, _)) in self.lines().iter().enumerate() {
+            if i != 0 {
+                writeln!(f)?;
+            }
+            write!(f, "    {:<10}{}", format!("{}:", name), value)?;
+        }
+        Ok(())
+    }
+}
@@ -1,187 +1,722 @@
 //! A module for dealing with ELF files
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
 
-use goblin::elf::header::{ET_DYN, ET_EXEC};
-use goblin::elf::program_header::{PT_INTERP, PT_LOAD};
-use goblin::elf::section_header::SHN_UNDEF;
-use goblin::elf::Elf as GoblinElf;
+use goblin::elf::dynamic::{
+    Dyn, DF_1_NOW, DF_BIND_NOW, DT_BIND_NOW, DT_FLAGS, DT_FLAGS_1, DT_PLTGOT, DT_RPATH, DT_RUNPATH,
+    DT_STRTAB,
+};
+use goblin::elf::header::{Header, EI_CLASS, EI_DATA, ELFCLASS64, ELFDATA2MSB, ET_DYN, ET_EXEC};
+use goblin::elf::header::{
+    EM_386, EM_68K, EM_AARCH64, EM_ARM, EM_MIPS, EM_PPC, EM_PPC64, EM_RISCV, EM_S390, EM_SPARC,
+    EM_SPARC32PLUS, EM_SPARCV9, EM_X86_64,
+};
+use goblin::elf::program_header::{PF_X, PT_GNU_RELRO, PT_GNU_STACK, PT_INTERP, PT_LOAD};
+use goblin::elf::section_header::{SHF_ALLOC, SHN_UNDEF, SHT_NOBITS};
+use goblin::elf::{Elf as GoblinElf, ProgramHeader, SectionHeader};
+use memmap::Mmap;
 use once_cell::sync::OnceCell;
 
+use crate::context::{self, Arch, Bits, Endianness, Machine};
+use crate::elf::checksec::{cet_features, NT_GNU_PROPERTY_TYPE_0};
+use crate::elf::plt;
+use crate::elf::{Checksec, Pattern, Relro, Search, Section, Segment};
+use crate::error::{Error, Result};
+
+/// The bytes backing an [`Elf`].
+enum Data<'a> {
+    Borrowed(&'a [u8]),
+    Mapped(Mmap),
+    Owned(Vec<u8>),
+}
+
+impl Deref for Data<'_> {
+    type Target = [u8];
+    fn deref(&self) -> &[u8] {
+        match self {
+            Data::Borrowed(bytes) => bytes,
+            Data::Mapped(map) => map,
+            Data::Owned(bytes) => bytes,
+        }
+    }
+}
+
+/// The tables of an ELF which are read after construction, copied out of the parsed file so
+/// that it doesn't need to be parsed again.
+struct Tables {
+    /// Named entries of `.symtab`, followed by those of `.dynsym`, with their values
+    symbols: Vec<(String, u64)>,
+    /// The name of each `.dynsym` entry, by index
+    dynamic_symbols: Vec<String>,
+    /// The target and symbol index of relocations in sections linked to a symbol table
+    section_relocs: Vec<(u64, usize)>,
+    /// The target and symbol index of the dynamic relocations
+    dynamic_relocs: Vec<(u64, usize)>,
+    dynamic: Vec<Dyn>,
+    rpath: Option<String>,
+    runpath: Option<String>,
+    /// Whether SHSTK and IBT are enabled by a GNU property note
+    cet: (bool, bool),
+}
+
+impl Tables {
+    fn new(elf: &GoblinElf, data: &[u8]) -> Self {
+        let symbols = (elf.syms.iter().map(|sym| (&elf.strtab, sym)))
+            .chain(elf.dynsyms.iter().map(|sym| (&elf.dynstrtab, sym)))
+            .filter_map(|(strtab, sym)| {
+                let name = strtab.get_at(sym.st_name).filter(|n| !n.is_empty())?;
+                Some((name.to_string(), sym.st_value))
+            })
+            .collect();
+        let dynamic_symbols = elf
+            .dynsyms
+            .iter()
+            .map(|sym| elf.dynstrtab.get_at(sym.st_name).unwrap_or("").to_string())
+            .collect();
+        let section_relocs = elf
+            .shdr_relocs
+            .iter()
+            .filter(|(idx, _)| elf.section_headers[*idx].sh_link != SHN_UNDEF)
+            .flat_map(|(_, sec)| sec.iter())
+            .map(|reloc| (reloc.r_offset, reloc.r_sym))
+            .collect();
+        let dynamic_relocs = (elf.pltrelocs.iter())
+            .chain(elf.dynrelas.iter())
+            .chain(elf.dynrels.iter())
+            .map(|reloc| (reloc.r_offset, reloc.r_sym))
+            .collect();
+
+        let mut cet = (false, false);
+        for note in elf.iter_note_headers(data).into_iter().flatten() {
+            let Ok(note) = note else {
+                continue;
+            };
+            if note.n_type == NT_GNU_PROPERTY_TYPE_0 && note.name.trim_end_matches('\0') == "GNU" {
+                let align = if elf.is_64 { 8 } else { 4 };
+                let features = cet_features(note.desc, align, !elf.little_endian);
+                cet.0 |= features.0;
+                cet.1 |= features.1;
+            }
+        }
+
+        Self {
+            symbols,
+            dynamic_symbols,
+            section_relocs,
+            dynamic_relocs,
+            dynamic: elf.dynamic.as_ref().map_or(Vec::new(), |d| d.dyns.clone()),
+            rpath: elf.rpaths.first().map(|s| s.to_string()),
+            runpath: elf.runpaths.first().map(|s| s.to_string()),
+            cet,
+        }
+    }
+    // The name of a `.dynsym` entry, if it has one
+    fn dynamic_symbol(&self, idx: usize) -> Option<&str> {
+        self.dynamic_symbols
+            .get(idx)
+            .map(String::as_str)
+            .filter(|name| !name.is_empty())
+    }
+}
+
 /// Wrapper around [`goblin::elf::Elf`].
+///
+/// The `Elf` owns (or borrows, for [`Elf::from_bytes`]) the bytes it was parsed from, which are
+/// released when it is dropped.
 pub struct Elf<'a> {
     path: PathBuf,
-    elf: GoblinElf<'a>,
-    symbols: OnceCell<HashMap<&'a str, usize>>,
-    got: OnceCell<HashMap<&'a str, usize>>,
-    plt: OnceCell<HashMap<&'a str, usize>>,
+    data: Data<'a>,
+    header: Header,
+    program_headers: Vec<ProgramHeader>,
+    section_headers: Vec<(String, SectionHeader)>,
+    tables: Tables,
+    symbols: OnceCell<HashMap<String, usize>>,
+    got: OnceCell<HashMap<String, usize>>,
+    plt: OnceCell<HashMap<String, usize>>,
     statically_linked: bool,
+    base_address: usize,
     address: usize,
 }
 
 impl<'a> Elf<'a> {
     /// Create a new [`Elf`] which is loaded from the given path.
-    pub fn new(path: impl Into<PathBuf>) -> Self {
+    ///
+    /// # Panics
+    ///
+    /// Panics if the file cannot be read or is not a valid ELF. See [`Elf::open`].
+    pub fn new(path: impl Into<PathBuf>) -> Elf<'static> {
+        Elf::open(path).expect("Could not load ELF file")
+    }
+    /// Load an [`Elf`] from the given path.
+    /// Returns an [`Error`](crate::Error) if the file cannot be mapped or parsed.
+    pub fn open(path: impl Into<PathBuf>) -> Result<Elf<'static>> {
         let path = path.into();
-        let mapped = Box::new(
-            unsafe {
-                memmap::MmapOptions::new()
-                    .map(&std::fs::File::open(&path).expect("Could not open file"))
-            }
-            .expect("Could not mmap file"),
-        );
-        let mapped = Box::leak(mapped);
-        Self::construct(path, mapped)
+        let mapped = unsafe { memmap::MmapOptions::new().map(&std::fs::File::open(&path)?) }?;
+        Elf::construct(path, Data::Mapped(mapped))
     }
     /// Create a new [`Elf`] from an array of raw bytes.
     /// The [`Elf::path`] will be set to an empty string.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bytes` is not a valid ELF. See [`Elf::parse`].
     pub fn from_bytes(bytes: &'a [u8]) -> Self {
-        Self::construct("".into(), bytes)
-    }
-    fn construct(path: PathBuf, bytes: &'a [u8]) -> Self {
-        let internal = GoblinElf::parse(bytes).expect("Not a valid ELF file");
-        let mut load_address = 0;
-        if internal.header.e_type != ET_DYN {
-            internal
-                .program_headers
-                .iter()
-                .filter(|seg| seg.p_type == PT_LOAD)
-                .for_each(|seg| {
-                    let addr = seg.p_vaddr;
-                    if addr != 0 && (addr < load_address || load_address == 0) {
-                        load_address = addr;
-                    }
-                });
-        }
-        let mut statically_linked = internal.header.e_type == ET_EXEC && load_address != 0;
-        if internal
-            .program_headers
-            .iter()
-            .any(|seg| seg.p_type == PT_INTERP)
-        {
-            statically_linked = false;
-        }
-
-        Self {
+        Self::parse(bytes).expect("Not a valid ELF file")
+    }
+    /// Parse an [`Elf`] from an array of raw bytes.
+    /// Returns an [`Error`](crate::Error) if they are not a valid ELF.
+    /// The [`Elf::path`] will be set to an empty string.
+    pub fn parse(bytes: &'a [u8]) -> Result<Self> {
+        Self::construct("".into(), Data::Borrowed(bytes))
+    }
+    /// Parse an [`Elf`] which takes ownership of the given bytes.
+    /// Returns an [`Error`](crate::Error) if they are not a valid ELF.
+    /// The [`Elf::path`] will be set to an empty string.
+    pub fn parse_vec(bytes: Vec<u8>) -> Result<Elf<'static>> {
+        Elf::construct("".into(), Data::Owned(bytes))
+    }
+    fn construct(path: PathBuf, data: Data<'a>) -> Result<Self> {
+        let internal = GoblinElf::parse(&data)?;
+        let (load_address, statically_linked) = layout(&internal);
+        let section_headers = named_sections(&internal);
+        let tables = Tables::new(&internal, &data);
+        let header = internal.header;
+        let program_headers = internal.program_headers;
+        Ok(Self {
             path,
-            elf: internal,
+            data,
+            header,
+            program_headers,
+            section_headers,
+            tables,
             symbols: Default::default(),
             got: Default::default(),
             plt: Default::default(),
             statically_linked,
-            address: load_address as usize,
+            base_address: load_address,
+            address: load_address,
+        })
+    }
+    // Write patches at file offsets. The patched ELF is re-parsed so that the cached headers and
+    // lookups stay valid, and the patches are reverted if it no longer parses.
+    fn patch(&mut self, patches: &[(usize, &[u8])]) -> Result<()> {
+        if let Some(&(offset, bytes)) = patches
+            .iter()
+            .find(|(offset, bytes)| offset + bytes.len() > self.data.len())
+        {
+            return Err(Error::NoSpace {
+                needed: offset + bytes.len(),
+                available: self.data.len(),
+            });
+        }
+        if !matches!(self.data, Data::Owned(_)) {
+            self.data = Data::Owned(self.data.to_vec());
+        }
+        let Data::Owned(data) = &mut self.data else {
+            unreachable!()
+        };
+        let originals: Vec<(usize, Vec<u8>)> = patches
+            .iter()
+            .map(|&(offset, bytes)| {
+                let range = offset..offset + bytes.len();
+                let original = data[range.clone()].to_vec();
+                data[range].copy_from_slice(bytes);
+                (offset, original)
+            })
+            .collect();
+        match GoblinElf::parse(data) {
+            Ok(internal) => {
+                let (load_address, statically_linked) = layout(&internal);
+                // Follow the new load address, unless the ELF has been rebased elsewhere
+                if self.address == self.base_address {
+                    self.address = load_address;
+                }
+                self.base_address = load_address;
+                self.statically_linked = statically_linked;
+                self.section_headers = named_sections(&internal);
+                self.tables = Tables::new(&internal, data);
+                self.header = internal.header;
+                self.program_headers = internal.program_headers;
+                self.symbols = OnceCell::new();
+                self.got = OnceCell::new();
+                self.plt = OnceCell::new();
+                Ok(())
+            }
+            Err(e) => {
+                for (offset, original) in originals.into_iter().rev() {
+                    data[offset..offset + original.len()].copy_from_slice(&original);
+                }
+                Err(e.into())
+            }
         }
     }
     /// The path the ELF file was originally loaded from.
     pub fn path(&self) -> &PathBuf {
         &self.path
     }
+    /// The endianness of the ELF file.
+    pub fn endian(&self) -> Endianness {
+        if self.header.e_ident[EI_DATA] == ELFDATA2MSB {
+            Endianness::Big
+        } else {
+            Endianness::Little
+        }
+    }
+    /// The architecture of the ELF file, derived from its machine type, class and endianness.
+    ///
+    /// Returns `None` for machine types without an [`Arch`].
+    pub fn arch(&self) -> Option<Arch> {
+        let is_64 = self.bits() == 64;
+        let machine = match (self.header.e_machine, is_64) {
+            (EM_386, _) => Machine::I386,
+            (EM_X86_64, _) => Machine::Amd64,
+            (EM_ARM, _) => Machine::Arm,
+            (EM_AARCH64, _) => Machine::Aarch64,
+            (EM_MIPS, false) => Machine::Mips,
+            (EM_MIPS, true) => Machine::Mips64,
+            (EM_PPC, _) => Machine::Powerpc,
+            (EM_PPC64, _) => Machine::Powerpc64,
+            (EM_RISCV, false) => Machine::Riscv32,
+            (EM_RISCV, true) => Machine::Riscv64,
+            (EM_SPARC | EM_SPARC32PLUS, _) => Machine::Sparc,
+            (EM_SPARCV9, _) => Machine::Sparc64,
+            (EM_S390, true) => Machine::S390x,
+            (EM_68K, _) => Machine::M68k,
+            _ => return None,
+        };
+        Some(Arch {
+            machine,
+            endian: self.endian(),
+            bits: if is_64 {
+                Bits::SixtyFour
+            } else {
+                Bits::ThirtyTwo
+            },
+        })
+    }
+    /// Configure the thread's [`context`] to match the ELF's architecture, so that packing
+    /// functions such as [`crate::pack`] and [`crate::flat()`] match the target.
+    ///
+    /// If the architecture is unknown, only the endianness and word size are set.
+    pub fn set_context(&self) {
+        match self.arch() {
+            Some(arch) => context::set_arch(arch),
+            None => {
+                context::set_endianess(self.endian());
+                context::set_bits(if self.bits() == 64 {
+                    Bits::SixtyFour
+                } else {
+                    Bits::ThirtyTwo
+                });
+            }
+        }
+    }
     /// The word size of the ELF file.
     pub fn bits(&self) -> usize {
-        if self.elf.is_64 {
+        if self.header.e_ident[EI_CLASS] == ELFCLASS64 {
             64
         } else {
             32
         }
     }
 
-    /// A name->address mapping of the symbols in the ELF.
-    pub fn symbols(&self) -> &HashMap<&'a str, usize> {
-        self.symbols.get_or_init(|| self.populate_symbols())
+    // Translate an address in the file to one relative to the current load address
+    fn rebase(&self, vaddr: u64) -> usize {
+        (vaddr as i64 + self.address as i64 - self.base_address as i64) as usize
     }
-    // Used to lazily populate the symbols map
-    fn populate_symbols(&self) -> HashMap<&'a str, usize> {
-        let mut syms = HashMap::new();
-        for sym in &self.elf.syms {
-            if sym.st_value == 0 {
-                continue;
+
+    /// The sections of the ELF, in the order of the section header table.
+    pub fn sections(&self) -> impl Iterator<Item = Section<'_>> + '_ {
+        self.section_headers.iter().map(|(name, header)| {
+            let data = if header.sh_type == SHT_NOBITS {
+                &[]
+            } else {
+                let start = header.sh_offset as usize;
+                self.data
+                    .get(start..start.saturating_add(header.sh_size as usize))
+                    .unwrap_or(&[])
+            };
+            let address = if header.sh_flags & SHF_ALLOC as u64 != 0 {
+                self.rebase(header.sh_addr)
+            } else {
+                header.sh_addr as usize
+            };
+            Section {
+                name,
+                header,
+                data,
+                address,
             }
-            let name = self.elf.strtab.get_at(sym.st_name).unwrap_or("");
-            if name.is_empty() {
-                continue;
+        })
+    }
+    /// Find a section by its name, such as `.bss`.
+    pub fn get_section_by_name(&self, name: &str) -> Option<Section<'_>> {
+        self.sections().find(|sec| sec.name() == name)
+    }
+    /// The segments of the ELF, in the order of the program header table.
+    pub fn segments(&self) -> impl Iterator<Item = Segment<'_>> + '_ {
+        self.program_headers.iter().map(|header| {
+            let start = header.p_offset as usize;
+            let data = self
+                .data
+                .get(start..start.saturating_add(header.p_filesz as usize))
+                .unwrap_or(&[]);
+            Segment {
+                header,
+                data,
+                address: self.rebase(header.p_vaddr),
+            }
+        })
+    }
+    /// The loaded (`PT_LOAD`) segments which are mapped executable.
+    pub fn executable_segments(&self) -> impl Iterator<Item = Segment<'_>> + '_ {
+        self.segments()
+            .filter(|seg| seg.p_type() == PT_LOAD && seg.is_executable())
+    }
+    /// The loaded (`PT_LOAD`) segments which are mapped writable.
+    pub fn writable_segments(&self) -> impl Iterator<Item = Segment<'_>> + '_ {
+        self.segments()
+            .filter(|seg| seg.p_type() == PT_LOAD && seg.is_writable())
+    }
+
+    // The loaded segment which contains the given (rebased) address in memory
+    fn loaded_segment(&self, address: usize) -> Option<Segment<'_>> {
+        self.segments().find(|seg| {
+            seg.p_type() == PT_LOAD
+                && address >= seg.address()
+                && address - seg.address() < seg.size()
+        })
+    }
+    /// Translate a virtual address to an offset in the file.
+    /// Returns `None` if the address is not backed by the file, for example inside `.bss`.
+    pub fn vaddr_to_offset(&self, address: usize) -> Option<usize> {
+        let seg = self.loaded_segment(address)?;
+        let delta = address - seg.address();
+        (delta < seg.file_size()).then(|| seg.offset() + delta)
+    }
+    /// Translate an offset in the file to a virtual address.
+    /// Returns `None` if the offset is not inside a loaded segment.
+    pub fn offset_to_vaddr(&self, offset: usize) -> Option<usize> {
+        self.segments()
+            .find(|seg| {
+                seg.p_type() == PT_LOAD
+                    && offset >= seg.offset()
+                    && offset - seg.offset() < seg.file_size()
+            })
+            .map(|seg| seg.address() + (offset - seg.offset()))
+    }
+    /// Read `count` bytes from the given virtual address.
+    /// Memory which is not backed by the file (such as `.bss`) reads as zeroes.
+    pub fn read(&self, address: usize, count: usize) -> Result<Vec<u8>> {
+        let mut out = Vec::with_capacity(count);
+        while out.len() < count {
+            let addr = address + out.len();
+            let seg = self
+                .loaded_segment(addr)
+                .ok_or(Error::UnmappedAddress(addr))?;
+            let start = addr - seg.address();
+            let end = seg.size().min(start + count - out.len());
+            out.extend((start..end).map(|i| seg.data().get(i).copied().unwrap_or(0)));
+        }
+        Ok(out)
+    }
+    /// Unpack a word from the given virtual address in a [`crate::context`] sensitive way.
+    pub fn unpack(&self, address: usize) -> Result<u64> {
+        let bytes = self.read(address, context::get_bits() as usize / 8)?;
+        Ok(crate::unpack(&bytes))
+    }
+    /// Read a NUL-terminated string from the given virtual address, excluding the terminator.
+    pub fn string(&self, address: usize) -> Result<Vec<u8>> {
+        let mut out = Vec::new();
+        loop {
+            let addr = address + out.len();
+            let seg = self
+                .loaded_segment(addr)
+                .ok_or(Error::UnmappedAddress(addr))?;
+            for i in addr - seg.address()..seg.size() {
+                match seg.data().get(i).copied().unwrap_or(0) {
+                    0 => return Ok(out),
+                    b => out.push(b),
+                }
             }
-            syms.insert(name, sym.st_value as usize);
         }
-        for sym in &self.elf.dynsyms {
-            if sym.st_value == 0 {
-                continue;
+    }
+
+    /// Report the exploit mitigations present in the ELF.
+    pub fn checksec(&self) -> Checksec {
+        let dyns = &self.tables.dynamic;
+        let flag = |tag, bit| dyns.iter().any(|d| d.d_tag == tag && d.d_val & bit != 0);
+        let bind_now = dyns.iter().any(|d| d.d_tag == DT_BIND_NOW)
+            || flag(DT_FLAGS, DF_BIND_NOW)
+            || flag(DT_FLAGS_1, DF_1_NOW);
+        let relro = match (self.has_segment(PT_GNU_RELRO), bind_now) {
+            (false, _) => Relro::None,
+            (true, false) => Relro::Partial,
+            (true, true) => Relro::Full,
+        };
+        let nx = self
+            .program_headers
+            .iter()
+            .any(|phdr| phdr.p_type == PT_GNU_STACK && phdr.p_flags & PF_X == 0);
+
+        let mut names: Vec<&str> = (self.tables.symbols.iter())
+            .map(|(name, _)| name.as_str())
+            .collect();
+        names.sort_unstable();
+        names.dedup();
+        let canary = names.iter().any(|name| {
+            matches!(
+                *name,
+                "__stack_chk_fail" | "__stack_chk_guard" | "__intel_security_cookie"
+            )
+        });
+        let fortified = names
+            .iter()
+            .filter(|name| **name != "__stack_chk_fail")
+            .filter_map(|name| name.strip_prefix("__")?.strip_suffix("_chk"))
+            .map(String::from)
+            .collect();
+
+        let (shstk, ibt) = self.tables.cet;
+
+        Checksec {
+            arch: self.arch(),
+            relro,
+            canary,
+            nx,
+            pie: self.header.e_type == ET_DYN,
+            address: self.address,
+            rpath: self.tables.rpath.clone(),
+            runpath: self.tables.runpath.clone(),
+            fortified,
+            shstk,
+            ibt,
+            stripped: !self
+                .section_headers
+                .iter()
+                .any(|(name, _)| name == ".symtab"),
+        }
+    }
+    // Whether the ELF has a segment of the given type
+    fn has_segment(&self, p_type: u32) -> bool {
+        self.program_headers
+            .iter()
+            .any(|phdr| phdr.p_type == p_type)
+    }
+
+    /// Search the loaded segments for a byte pattern, returning an iterator over the virtual
+    /// addresses where it occurs. See [`Search`] for filtering the results.
+    pub fn search(&self, needle: impl Into<Pattern>) -> Search<'_> {
+        Search::new(needle.into(), self.segments())
+    }
+    /// Write bytes to the given virtual address.
+    /// The ELF's bytes are copied on the first write, so the original file is left untouched until
+    /// [`Elf::save`] is called. Later reads through the `Elf` reflect the patch.
+    pub fn write(&mut self, address: usize, bytes: &[u8]) -> Result<()> {
+        let mut patches = Vec::new();
+        let mut written = 0;
+        while written < bytes.len() {
+            let addr = address + written;
+            let seg = self
+                .loaded_segment(addr)
+                .ok_or(Error::UnmappedAddress(addr))?;
+            let start = addr - seg.address();
+            // Memory which isn't backed by the file can't be patched
+            if start >= seg.file_size() {
+                return Err(Error::UnmappedAddress(addr));
+            }
+            let count = (seg.file_size() - start).min(bytes.len() - written);
+            patches.push((seg.offset() + start, &bytes[written..written + count]));
+            written += count;
+        }
+        self.patch(&patches)
+    }
+    /// Pack a word to the given virtual address in a [`crate::context`] sensitive way.
+    pub fn pack<T>(&mut self, address: usize, value: T) -> Result<()>
+    where
+        T: num_traits::ToPrimitive,
+    {
+        let bytes = crate::pack(value).ok_or_else(|| Error::ValueTooLarge(context::get_bits()))?;
+        self.write(address, &bytes)
+    }
+    /// Save the (possibly patched) ELF to the given path.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        Ok(std::fs::write(path, &*self.data)?)
+    }
+    /// Replace the path of the program interpreter (dynamic linker).
+    /// The new path must fit in the existing `PT_INTERP` segment.
+    pub fn patch_interpreter(&mut self, interpreter: &str) -> Result<()> {
+        let seg = self
+            .segments()
+            .find(|seg| seg.p_type() == PT_INTERP)
+            .ok_or(Error::Missing("PT_INTERP segment"))?;
+        let (offset, available) = (seg.offset(), seg.file_size());
+        let mut bytes = interpreter.as_bytes().to_vec();
+        if bytes.len() + 1 > available {
+            return Err(Error::NoSpace {
+                needed: bytes.len() + 1,
+                available,
+            });
+        }
+        bytes.resize(available, 0);
+        self.patch(&[(offset, &bytes)])
+    }
+    /// Replace the library search path set by `DT_RUNPATH` (or `DT_RPATH`).
+    /// The ELF must already have one of these entries, and the new path must fit in the space used
+    /// by the old one.
+    pub fn set_rpath(&mut self, rpath: &str) -> Result<()> {
+        let (strtab, entry) = {
+            let dyns = &self.tables.dynamic;
+            if dyns.is_empty() {
+                return Err(Error::Missing("dynamic segment"));
             }
-            let name = self.elf.dynstrtab.get_at(sym.st_name).unwrap_or("");
-            if name.is_empty() {
+            let strtab = dyns
+                .iter()
+                .find(|d| d.d_tag == DT_STRTAB)
+                .ok_or(Error::Missing("DT_STRTAB entry"))?
+                .d_val;
+            let entry = dyns
+                .iter()
+                .find(|d| d.d_tag == DT_RUNPATH || d.d_tag == DT_RPATH)
+                .ok_or(Error::Missing("DT_RUNPATH or DT_RPATH entry"))?
+                .d_val;
+            (strtab, entry)
+        };
+        let strtab = self.rebase(strtab);
+        let offset = self
+            .vaddr_to_offset(strtab)
+            .ok_or(Error::UnmappedAddress(strtab))?
+            + entry as usize;
+        let available = self.data[offset.min(self.data.len())..]
+            .iter()
+            .position(|&b| b == 0)
+            .ok_or(Error::Missing("terminated DT_RUNPATH string"))?
+            + 1;
+        let mut bytes = rpath.as_bytes().to_vec();
+        if bytes.len() + 1 > available {
+            return Err(Error::NoSpace {
+                needed: bytes.len() + 1,
+                available,
+            });
+        }
+        bytes.resize(available, 0);
+        self.patch(&[(offset, &bytes)])
+    }
+    /// Make the stack executable by setting `PF_X` on the `PT_GNU_STACK` segment.
+    /// Without a `PT_GNU_STACK` segment, the stack is already executable.
+    pub fn disable_nx(&mut self) -> Result<()> {
+        let Some(idx) = self
+            .program_headers
+            .iter()
+            .position(|phdr| phdr.p_type == PT_GNU_STACK)
+        else {
+            return Ok(());
+        };
+        let flags = self.program_headers[idx].p_flags | PF_X;
+        // `p_flags` is the second field of a 64-bit program header, and the seventh of a 32-bit one
+        let field = if self.bits() == 64 { 4 } else { 24 };
+        let offset = self.header.e_phoff as usize + idx * self.header.e_phentsize as usize + field;
+        let bytes = if self.endian() == Endianness::Big {
+            flags.to_be_bytes()
+        } else {
+            flags.to_le_bytes()
+        };
+        self.patch(&[(offset, &bytes)])
+    }
+
+    /// A name->address mapping of the symbols in the ELF.
+    pub fn symbols(&self) -> &HashMap<String, usize> {
+        self.symbols.get_or_init(|| self.populate_symbols())
+    }
+    // Used to lazily populate the symbols map
+    fn populate_symbols(&self) -> HashMap<String, usize> {
+        let mut syms = HashMap::new();
+        for (name, value) in &self.tables.symbols {
+            if *value == 0 {
                 continue;
             }
-            syms.insert(name, sym.st_value as usize);
+            syms.insert(name.clone(), self.rebase(*value));
         }
         for (name, addr) in self.plt() {
             if !syms.contains_key(name) {
-                syms.insert(name, *addr);
+                syms.insert(name.clone(), *addr);
             }
         }
         for (name, addr) in self.got() {
             if !syms.contains_key(name) {
-                syms.insert(name, *addr);
+                syms.insert(name.clone(), *addr);
             }
         }
         syms
     }
 
     /// A name->address mapping of the GOT entries in the ELF.
-    pub fn got(&self) -> &HashMap<&'a str, usize> {
+    pub fn got(&self) -> &HashMap<String, usize> {
         self.got.get_or_init(|| self.populate_got())
     }
     // Used to lazily populate the GOT map
-    fn populate_got(&self) -> HashMap<&'a str, usize> {
+    fn populate_got(&self) -> HashMap<String, usize> {
         if self.statically_linked {
             return Default::default();
         }
         let mut got = HashMap::new();
-        for (idx, sec) in &self.elf.shdr_relocs {
-            let shdr = &self.elf.section_headers[*idx];
-            if shdr.sh_link == SHN_UNDEF {
+        for &(offset, sym) in &self.tables.section_relocs {
+            if sym == 0 || offset == 0 {
                 continue;
             }
-            for reloc in sec.iter() {
-                let sym = reloc.r_sym;
-                if sym == 0 {
-                    continue;
-                }
-                if let Some(sym) = self.elf.dynsyms.get(sym) {
-                    if let Some(name) = self.elf.dynstrtab.get_at(sym.st_name) {
-                        if name.is_empty() || reloc.r_offset == 0 {
-                            continue;
-                        }
-                        got.insert(name, reloc.r_offset as usize);
-                    }
-                }
+            if let Some(name) = self.tables.dynamic_symbol(sym) {
+                got.insert(name.to_string(), self.rebase(offset));
             }
         }
         got
     }
 
     /// A name->address mapping of the PLT entries in the ELF.
-    pub fn plt(&self) -> &HashMap<&'a str, usize> {
+    ///
+    /// Stubs in `.plt.sec`, `.plt`, `.plt.got` and `.MIPS.stubs` are decoded to find the GOT slot
+    /// they jump through, for x86, ARM, AArch64 and MIPS.
+    pub fn plt(&self) -> &HashMap<String, usize> {
         self.plt.get_or_init(|| self.populate_plt())
     }
     // Used to lazily populate the PLT map
-    fn populate_plt(&self) -> HashMap<&'a str, usize> {
-        if self.statically_linked || self.got().is_empty() {
+    fn populate_plt(&self) -> HashMap<String, usize> {
+        if self.statically_linked {
             return Default::default();
         }
-        let plt_section = self
-            .elf
-            .section_headers
-            .iter()
-            .find(|shdr| matches!(self.elf.shdr_strtab.get_at(shdr.sh_name), Some(".plt")))
-            .expect("No .plt section");
+        let name_of = |sym: usize| self.tables.dynamic_symbol(sym);
+        // The symbol resolved into each GOT slot
+        let slots: HashMap<u64, &str> = (self.tables.dynamic_relocs.iter())
+            .filter_map(|&(slot, sym)| Some((slot, name_of(sym)?)))
+            .collect();
+        let got = (self.tables.dynamic.iter())
+            .find(|d| d.d_tag == DT_PLTGOT)
+            .map_or(0, |d| d.d_val);
 
         let mut plt = HashMap::new();
-        for (i, reloc) in self.elf.pltrelocs.iter().enumerate() {
-            if let Some(sym) = self.elf.dynsyms.get(reloc.r_sym) {
-                if let Some(name) = self.elf.dynstrtab.get_at(sym.st_name) {
-                    plt.insert(
-                        name,
-                        (plt_section.sh_addr + (plt_section.sh_entsize * (i + 1) as u64)) as usize,
-                    );
+        // `.plt.sec` holds the stubs which are called when IBT is enabled, so takes precedence
+        for section in [".plt.sec", ".plt", ".plt.got", ".MIPS.stubs"] {
+            for (_, shdr) in self.section_headers.iter().filter(|(n, _)| n == section) {
+                let start = shdr.sh_offset as usize;
+                let code = plt::Code {
+                    machine: self.header.e_machine,
+                    little_endian: self.endian() == Endianness::Little,
+                    is_64: self.bits() == 64,
+                    got,
+                    address: shdr.sh_addr,
+                    data: (self.data)
+                        .get(start..start.saturating_add(shdr.sh_size as usize))
+                        .unwrap_or(&[]),
+                };
+                for stub in code.stubs() {
+                    let name = match stub.target {
+                        plt::Target::Slot(slot) => slots.get(&slot).copied(),
+                        plt::Target::Symbol(sym) => name_of(sym),
+                    };
+                    if let Some(name) = name {
+                        plt.entry(name.to_string())
+                            .or_insert(self.rebase(stub.address));
+                    }
                 }
             }
         }
@@ -193,28 +728,47 @@ impl<'a> Elf<'a> {
     }
     /// Set the ELF's load address, rebasing all values.
     pub fn set_address(&mut self, address: usize) -> usize {
-        let delta = address as i64 - self.address as i64;
         self.address = address;
-        // Ensure each map is initialized
-        self.symbols();
-        self.symbols
-            .get_mut()
-            .unwrap()
-            .iter_mut()
-            .for_each(|(_, v)| {
-                let tmp = *v as i64;
-                *v = (tmp + delta) as usize
-            });
-        self.got();
-        self.got.get_mut().unwrap().iter_mut().for_each(|(_, v)| {
-            let tmp = *v as i64;
-            *v = (tmp + delta) as usize
-        });
-        self.plt();
-        self.plt.get_mut().unwrap().iter_mut().for_each(|(_, v)| {
-            let tmp = *v as i64;
-            *v = (tmp + delta) as usize
-        });
+        // The lookups are rebased when they're populated
+        self.symbols = OnceCell::new();
+        self.got = OnceCell::new();
+        self.plt = OnceCell::new();
         address
     }
 }
+
+// The address an ELF is loaded at by default, and whether it is statically linked
+fn layout(elf: &GoblinElf) -> (usize, bool) {
+    let mut load_address = 0;
+    if elf.header.e_type != ET_DYN {
+        elf.program_headers
+            .iter()
+            .filter(|seg| seg.p_type == PT_LOAD)
+            .for_each(|seg| {
+                let addr = seg.p_vaddr;
+                if addr != 0 && (addr < load_address || load_address == 0) {
+                    load_address = addr;
+                }
+            });
+    }
+    let mut statically_linked = elf.header.e_type == ET_EXEC && load_address != 0;
+    if elf
+        .program_headers
+        .iter()
+        .any(|seg| seg.p_type == PT_INTERP)
+    {
+        statically_linked = false;
+    }
+    (load_address as usize, statically_linked)
+}
+
+// Resolve the name of each section header
+fn named_sections(elf: &GoblinElf) -> Vec<(String, SectionHeader)> {
+    elf.section_headers
+        .iter()
+        .map(|shdr| {
+            let name = elf.shdr_strtab.get_at(shdr.sh_name).unwrap_or("");
+            (name.to_string(), shdr.clone())
+        })
+        .collect()
+}
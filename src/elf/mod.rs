@@ -2,17 +2,84 @@
 //! # Examples
 //! ## Loading from a file
 //! ELF files are memory-mapped into the process, reducing their memory footprint.
+//! The mapping is released when the [`Elf`] is dropped.
 //! ```no_run
 //! use pwn::Elf;
 //! let e = Elf::new("/tmp/binary");
 //! dbg!(e.got().get("puts"));
 //! ```
 //!
+//! ## Loading from bytes
+//! An [`Elf`] can borrow a byte slice, or take ownership of a `Vec<u8>`.
 //! ```no_run
 //! use pwn::Elf;
 //! let e = Elf::from_bytes(b"\x7fELF...");
 //! dbg!(e.got().get("puts"));
 //! ```
+//! [`Elf::new`] and [`Elf::from_bytes`] panic on invalid input. [`Elf::open`] and [`Elf::parse`]
+//! return an [`Error`](crate::Error) instead, which is useful when scanning many files.
+//! ```no_run
+//! use pwn::Elf;
+//! for entry in std::fs::read_dir("/usr/lib").unwrap() {
+//!     if let Ok(e) = Elf::open(entry.unwrap().path()) {
+//!         dbg!(e.plt().get("puts"));
+//!     }
+//! }
+//! ```
+//! ## Architecture
+//! The target architecture is read from the ELF header. [`Elf::set_context`] configures the
+//! thread's [`context`](crate::context) so that packing matches the target.
+//! ```no_run
+//! use pwn::{p32, Elf, MIPS};
+//! let e = Elf::new("/tmp/mips-binary");
+//! assert_eq!(e.arch(), Some(MIPS));
+//! e.set_context();
+//! assert_eq!(p32(0x11223344), b"\x11\x22\x33\x44");
+//! ```
+//! ## Sections and segments
+//! ```no_run
+//! use pwn::Elf;
+//! let e = Elf::new("/tmp/binary");
+//! let bss = e.get_section_by_name(".bss").unwrap();
+//! dbg!(bss.address(), bss.size());
+//! for seg in e.executable_segments() {
+//!     dbg!(seg.address(), seg.data().len());
+//! }
+//! ```
+//! ## Reading memory
+//! Data can be read from virtual addresses, which are translated through the loaded segments.
+//! ```no_run
+//! use pwn::Elf;
+//! let e = Elf::new("/tmp/binary");
+//! let data = e.get_section_by_name(".data").unwrap().address();
+//! dbg!(e.unpack(data).unwrap(), e.string(data + 8).unwrap());
+//! ```
+//! ## Patching
+//! Patches are applied to a copy of the ELF's bytes, which can then be saved to disk.
+//! ```no_run
+//! use pwn::Elf;
+//! let mut e = Elf::new("/tmp/binary");
+//! let alarm = *e.plt().get("alarm").unwrap();
+//! e.write(alarm, b"\xc3").unwrap();
+//! e.patch_interpreter("/tmp/ld-2.27.so").unwrap();
+//! e.save("/tmp/binary.patched").unwrap();
+//! ```
+//! ## Mitigations
+//! ```no_run
+//! use pwn::{init_logger, Elf};
+//! init_logger();
+//! let e = Elf::new("/tmp/binary");
+//! e.checksec().log(format_args!("{:?}", e.path()));
+//! assert!(e.checksec().nx);
+//! ```
+//! ## Searching
+//! ```no_run
+//! use pwn::{Elf, Pattern};
+//! let e = Elf::new("/tmp/binary");
+//! let bin_sh = e.search("/bin/sh\0").next();
+//! let pop_rdi: Vec<usize> = e.search(b"\x5f\xc3").executable().collect();
+//! let mov = e.search(Pattern::from_hex("48 8b 05 ?? ?? ?? ??").unwrap()).next();
+//! ```
 //! Symbols in the ELF, GOT and PLT are lazily resolved on request, reducing initial load delay.
 //!
 //! ELF files can also have their load address changed, which will rebase all their values.
@@ -29,3 +96,10 @@
 #[allow(clippy::module_inception)]
 mod elf;
 pub use elf::Elf;
+mod checksec;
+pub use checksec::{Checksec, Relro};
+mod plt;
+mod search;
+pub use search::{Pattern, Search};
+mod section;
+pub use section::{Section, Segment};
@@ -0,0 +1,203 @@
+//! Decoding PLT stubs to find the GOT slot (or symbol) each one jumps through.
+use goblin::elf::header::{EM_386, EM_AARCH64, EM_ARM, EM_MIPS, EM_X86_64};
+
+/// Where a PLT stub transfers control to.
+pub(crate) enum Target {
+    /// The address of the GOT slot the stub jumps through.
+    Slot(u64),
+    /// The index of the dynamic symbol the stub resolves (used by `.MIPS.stubs`).
+    Symbol(usize),
+}
+
+/// A decoded PLT stub.
+pub(crate) struct Stub {
+    pub address: u64,
+    pub target: Target,
+}
+
+/// The layout of the code being decoded.
+pub(crate) struct Code<'d> {
+    pub machine: u16,
+    pub little_endian: bool,
+    pub is_64: bool,
+    /// The address of the GOT (`DT_PLTGOT`), which i386 PIC stubs are relative to.
+    pub got: u64,
+    pub address: u64,
+    pub data: &'d [u8],
+}
+
+impl Code<'_> {
+    // Read the instruction word at the given offset
+    fn word(&self, offset: usize) -> Option<u32> {
+        let bytes = self.data.get(offset..offset + 4)?.try_into().ok()?;
+        Some(if self.little_endian {
+            u32::from_le_bytes(bytes)
+        } else {
+            u32::from_be_bytes(bytes)
+        })
+    }
+
+    /// Find every stub in the code.
+    pub fn stubs(&self) -> Vec<Stub> {
+        match self.machine {
+            EM_386 | EM_X86_64 => self.x86(),
+            EM_ARM => self.arm(),
+            EM_AARCH64 => self.aarch64(),
+            EM_MIPS => self.mips(),
+            _ => Vec::new(),
+        }
+    }
+
+    // `jmp [rip + disp32]` on amd64, and `jmp [abs32]` or `jmp [ebx + disp32]` on i386,
+    // optionally preceded by `bnd` and `endbr64`/`endbr32`
+    fn x86(&self) -> Vec<Stub> {
+        let data = self.data;
+        let mut stubs = Vec::new();
+        for i in 0..data.len().saturating_sub(5) {
+            if data[i] != 0xff {
+                continue;
+            }
+            let disp = i32::from_le_bytes(data[i + 2..i + 6].try_into().unwrap()) as i64 as u64;
+            let slot = match (data[i + 1], self.is_64) {
+                (0x25, true) => self.address.wrapping_add(i as u64 + 6).wrapping_add(disp),
+                (0x25, false) => disp as u32 as u64,
+                (0xa3, false) => self.got.wrapping_add(disp) as u32 as u64,
+                _ => continue,
+            };
+            let mut start = i;
+            if start >= 1 && data[start - 1] == 0xf2 {
+                start -= 1;
+            }
+            if start >= 4 && matches!(data[start - 4..start], [0xf3, 0x0f, 0x1e, 0xfa | 0xfb]) {
+                start -= 4;
+            }
+            stubs.push(Stub {
+                address: self.address + start as u64,
+                target: Target::Slot(slot),
+            });
+        }
+        stubs
+    }
+
+    // `add ip, pc, #imm; add ip, ip, #imm...; ldr pc, [ip, #imm]!`, or the long form
+    // `ldr ip, [pc, #4]; add ip, ip, pc; ldr pc, [ip]; .word offset`, optionally preceded by a
+    // Thumb `bx pc; nop`
+    fn arm(&self) -> Vec<Stub> {
+        let imm = |insn: u32| (insn & 0xff).rotate_right(((insn >> 8) & 0xf) * 2) as u64;
+        let mut stubs = Vec::new();
+        for i in (0..self.data.len()).step_by(4) {
+            let pc = self.address + i as u64 + 8;
+            let slot = match self.word(i) {
+                Some(insn) if insn & 0x0fff_f000 == 0x028f_c000 => {
+                    let mut ip = pc + imm(insn);
+                    let mut j = i + 4;
+                    while let Some(insn) = self.word(j).filter(|w| w & 0x0fff_f000 == 0x028c_c000) {
+                        ip += imm(insn);
+                        j += 4;
+                    }
+                    match self.word(j) {
+                        Some(insn) if insn & 0x0fff_f000 == 0x05bc_f000 => {
+                            ip + (insn & 0xfff) as u64
+                        }
+                        _ => continue,
+                    }
+                }
+                Some(0xe59f_c004) => {
+                    match (self.word(i + 4), self.word(i + 8), self.word(i + 12)) {
+                        (Some(0xe08c_c00f), Some(0xe59c_f000), Some(offset)) => {
+                            (pc + 4).wrapping_add(offset as u64) as u32 as u64
+                        }
+                        _ => continue,
+                    }
+                }
+                _ => continue,
+            };
+            let mut start = i;
+            if start >= 4 && matches!(self.data[start - 4..start], [0x78, 0x47, 0xc0, 0x46]) {
+                start -= 4;
+            }
+            stubs.push(Stub {
+                address: self.address + start as u64,
+                target: Target::Slot(slot),
+            });
+        }
+        stubs
+    }
+
+    // `adrp x16, page; ldr x17, [x16, #offset]`, optionally preceded by `bti c`
+    fn aarch64(&self) -> Vec<Stub> {
+        let mut stubs = Vec::new();
+        for i in (0..self.data.len()).step_by(4) {
+            let (Some(adrp), Some(ldr)) = (self.word(i), self.word(i + 4)) else {
+                continue;
+            };
+            if adrp & 0x9f00_001f != 0x9000_0010 || ldr & 0xffc0_03ff != 0xf940_0211 {
+                continue;
+            }
+            let imm = (((adrp >> 5) & 0x7ffff) << 2) | ((adrp >> 29) & 3);
+            // Sign-extend the 21-bit page offset
+            let imm = ((imm << 11) as i32 >> 11) as i64 as u64;
+            let page = ((self.address + i as u64) & !0xfff).wrapping_add(imm << 12);
+            let slot = page + ((ldr >> 10) & 0xfff) as u64 * 8;
+            let mut start = i;
+            if start >= 4 && self.word(start - 4) == Some(0xd503_245f) {
+                start -= 4;
+            }
+            stubs.push(Stub {
+                address: self.address + start as u64,
+                target: Target::Slot(slot),
+            });
+        }
+        stubs
+    }
+
+    // `.plt` stubs are `lui t7, %hi(slot); lw t9, %lo(slot)(t7)`. `.MIPS.stubs` are
+    // `lw t9, (gp); move t7, ra; jalr t9; li t8, index`, where `index` may be built with `lui; ori`
+    fn mips(&self) -> Vec<Stub> {
+        let mut stubs = Vec::new();
+        for i in (0..self.data.len()).step_by(4) {
+            let Some(insn) = self.word(i) else {
+                continue;
+            };
+            let target = if insn & 0xffff_0000 == 0x3c0f_0000 {
+                match self.word(i + 4) {
+                    Some(load) if matches!(load & 0xffff_0000, 0x8df9_0000 | 0xddf9_0000) => {
+                        let hi = ((insn & 0xffff) << 16) as i32 as i64;
+                        let lo = (load & 0xffff) as i16 as i64;
+                        let slot = (hi + lo) as u64;
+                        Target::Slot(if self.is_64 { slot } else { slot as u32 as u64 })
+                    }
+                    _ => continue,
+                }
+            } else if matches!(insn & 0xffff_0000, 0x8f99_0000 | 0xdf99_0000) {
+                let mut index = None;
+                let mut high = 0;
+                for j in 1..=4 {
+                    match self.word(i + j * 4) {
+                        Some(w) if w & 0xffff_0000 == 0x3c18_0000 => high = (w & 0xffff) << 16,
+                        Some(w) if matches!(w & 0xffff_0000, 0x2418_0000 | 0x3418_0000) => {
+                            index = Some((w & 0xffff) as usize);
+                            break;
+                        }
+                        Some(w) if w & 0xffff_0000 == 0x3718_0000 => {
+                            index = Some((high | (w & 0xffff)) as usize);
+                            break;
+                        }
+                        _ => {}
+                    }
+                }
+                match index {
+                    Some(index) => Target::Symbol(index),
+                    None => continue,
+                }
+            } else {
+                continue;
+            };
+            stubs.push(Stub {
+                address: self.address + i as u64,
+                target,
+            });
+        }
+        stubs
+    }
+}
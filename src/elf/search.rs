@@ -0,0 +1,173 @@
+//! Searching for byte patterns in the segments of an [`Elf`](crate::Elf).
+use goblin::elf::program_header::PT_LOAD;
+
+use crate::elf::Segment;
+
+/// A byte pattern to search for, where each byte is compared under a mask.
+///
+/// # Examples
+/// ```
+/// use pwn::Pattern;
+/// // `pop rdi; ret`
+/// let exact = Pattern::from(b"\x5f\xc3");
+/// // `mov rax, [rip + ??]`
+/// let wildcard = Pattern::from_hex("48 8b 05 ?? ?? ?? ??").unwrap();
+/// assert_eq!(wildcard.len(), 7);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pattern {
+    bytes: Vec<u8>,
+    mask: Vec<u8>,
+}
+
+impl Pattern {
+    /// Create a pattern where only the bits set in `mask` are compared.
+    ///
+    /// # Panics
+    ///
+    /// Panics when `bytes` and `mask` have different lengths.
+    pub fn masked(bytes: impl Into<Vec<u8>>, mask: impl Into<Vec<u8>>) -> Self {
+        let (bytes, mask) = (bytes.into(), mask.into());
+        assert_eq!(bytes.len(), mask.len(), "Pattern and mask lengths differ");
+        Self { bytes, mask }
+    }
+    /// Parse a pattern from whitespace-separated hex bytes, where `??` matches any byte.
+    /// Returns `None` if the string is not valid.
+    pub fn from_hex(s: &str) -> Option<Self> {
+        let mut bytes = Vec::new();
+        let mut mask = Vec::new();
+        for byte in s.split_whitespace() {
+            if byte == "??" {
+                bytes.push(0);
+                mask.push(0);
+            } else if byte.len() == 2 {
+                bytes.push(u8::from_str_radix(byte, 16).ok()?);
+                mask.push(0xff);
+            } else {
+                return None;
+            }
+        }
+        Some(Self { bytes, mask })
+    }
+    /// The length of the pattern in bytes.
+    pub fn len(&self) -> usize {
+        self.bytes.len()
+    }
+    /// Whether the pattern is empty.
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+    /// Whether the pattern matches the start of `haystack`.
+    pub fn matches(&self, haystack: &[u8]) -> bool {
+        haystack.len() >= self.len()
+            && self
+                .bytes
+                .iter()
+                .zip(&self.mask)
+                .zip(haystack)
+                .all(|((b, m), h)| b & m == h & m)
+    }
+}
+
+impl From<Vec<u8>> for Pattern {
+    fn from(bytes: Vec<u8>) -> Self {
+        let mask = vec![0xff; bytes.len()];
+        Self { bytes, mask }
+    }
+}
+
+impl From<&[u8]> for Pattern {
+    fn from(bytes: &[u8]) -> Self {
+        bytes.to_vec().into()
+    }
+}
+
+impl<const N: usize> From<&[u8; N]> for Pattern {
+    fn from(bytes: &[u8; N]) -> Self {
+        bytes.to_vec().into()
+    }
+}
+
+impl From<&str> for Pattern {
+    fn from(s: &str) -> Self {
+        s.as_bytes().into()
+    }
+}
+
+/// An iterator over the virtual addresses where a [`Pattern`] occurs in an [`Elf`](crate::Elf).
+/// Created by [`Elf::search`](crate::Elf::search).
+///
+/// Only the file-backed contents of loaded segments are searched. Addresses follow
+/// [`Elf::set_address`](crate::Elf::set_address).
+pub struct Search<'e> {
+    pattern: Pattern,
+    segments: Vec<Segment<'e>>,
+    executable: bool,
+    writable: bool,
+    align: usize,
+    segment: usize,
+    position: usize,
+}
+
+impl<'e> Search<'e> {
+    pub(crate) fn new(pattern: Pattern, segments: impl Iterator<Item = Segment<'e>>) -> Self {
+        Self {
+            pattern,
+            segments: segments.filter(|seg| seg.p_type() == PT_LOAD).collect(),
+            executable: false,
+            writable: false,
+            align: 1,
+            segment: 0,
+            position: 0,
+        }
+    }
+    /// Only search segments which are mapped executable.
+    pub fn executable(mut self) -> Self {
+        self.executable = true;
+        self
+    }
+    /// Only search segments which are mapped writable.
+    pub fn writable(mut self) -> Self {
+        self.writable = true;
+        self
+    }
+    /// Only return addresses which are a multiple of `align`.
+    ///
+    /// # Panics
+    ///
+    /// Panics when `align` is 0.
+    pub fn aligned(mut self, align: usize) -> Self {
+        assert_ne!(align, 0, "Alignment must be non-zero");
+        self.align = align;
+        self
+    }
+}
+
+impl Iterator for Search<'_> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        if self.pattern.is_empty() {
+            return None;
+        }
+        loop {
+            let seg = self.segments.get(self.segment)?;
+            if (self.executable && !seg.is_executable()) || (self.writable && !seg.is_writable()) {
+                self.segment += 1;
+                self.position = 0;
+                continue;
+            }
+            let data = seg.data();
+            while self.position + self.pattern.len() <= data.len() {
+                let position = self.position;
+                self.position += 1;
+                let address = seg.address() + position;
+                if address.is_multiple_of(self.align) && self.pattern.matches(&data[position..]) {
+                    return Some(address);
+                }
+            }
+            self.segment += 1;
+            self.position = 0;
+        }
+    }
+}
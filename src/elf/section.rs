@@ -0,0 +1,112 @@
+//! Views into the sections and segments of an [`Elf`](crate::Elf).
+use goblin::elf::program_header::{PF_R, PF_W, PF_X};
+use goblin::elf::section_header::{SHF_ALLOC, SHF_EXECINSTR, SHF_WRITE};
+use goblin::elf::{ProgramHeader, SectionHeader};
+
+/// A section of an [`Elf`](crate::Elf), as described by its section header.
+#[derive(Clone, Copy, Debug)]
+pub struct Section<'e> {
+    pub(crate) name: &'e str,
+    pub(crate) header: &'e SectionHeader,
+    pub(crate) data: &'e [u8],
+    pub(crate) address: usize,
+}
+
+impl<'e> Section<'e> {
+    /// The name of the section, such as `.bss`.
+    pub fn name(&self) -> &'e str {
+        self.name
+    }
+    /// The raw section header.
+    pub fn header(&self) -> &'e SectionHeader {
+        self.header
+    }
+    /// The contents of the section in the file. Empty for `SHT_NOBITS` sections such as `.bss`.
+    pub fn data(&self) -> &'e [u8] {
+        self.data
+    }
+    /// The virtual address of the section, rebased with [`Elf::set_address`](crate::Elf::set_address).
+    /// Sections which are not loaded into memory keep their original address.
+    pub fn address(&self) -> usize {
+        self.address
+    }
+    /// The size of the section in memory.
+    pub fn size(&self) -> usize {
+        self.header.sh_size as usize
+    }
+    /// The offset of the section in the file.
+    pub fn offset(&self) -> usize {
+        self.header.sh_offset as usize
+    }
+    /// The raw `SHF_*` flags of the section.
+    pub fn flags(&self) -> u64 {
+        self.header.sh_flags
+    }
+    /// Whether the section occupies memory at runtime.
+    pub fn is_alloc(&self) -> bool {
+        self.header.sh_flags & SHF_ALLOC as u64 != 0
+    }
+    /// Whether the section is writable at runtime.
+    pub fn is_writable(&self) -> bool {
+        self.header.sh_flags & SHF_WRITE as u64 != 0
+    }
+    /// Whether the section contains executable instructions.
+    pub fn is_executable(&self) -> bool {
+        self.header.sh_flags & SHF_EXECINSTR as u64 != 0
+    }
+}
+
+/// A segment of an [`Elf`](crate::Elf), as described by its program header.
+#[derive(Clone, Copy, Debug)]
+pub struct Segment<'e> {
+    pub(crate) header: &'e ProgramHeader,
+    pub(crate) data: &'e [u8],
+    pub(crate) address: usize,
+}
+
+impl<'e> Segment<'e> {
+    /// The raw program header.
+    pub fn header(&self) -> &'e ProgramHeader {
+        self.header
+    }
+    /// The `PT_*` type of the segment.
+    pub fn p_type(&self) -> u32 {
+        self.header.p_type
+    }
+    /// The contents of the segment in the file.
+    pub fn data(&self) -> &'e [u8] {
+        self.data
+    }
+    /// The virtual address of the segment, rebased with [`Elf::set_address`](crate::Elf::set_address).
+    pub fn address(&self) -> usize {
+        self.address
+    }
+    /// The size of the segment in memory.
+    pub fn size(&self) -> usize {
+        self.header.p_memsz as usize
+    }
+    /// The size of the segment in the file.
+    pub fn file_size(&self) -> usize {
+        self.header.p_filesz as usize
+    }
+    /// The offset of the segment in the file.
+    pub fn offset(&self) -> usize {
+        self.header.p_offset as usize
+    }
+    /// The raw `PF_*` flags of the segment.
+    pub fn flags(&self) -> u32 {
+        self.header.p_flags
+    }
+    /// Whether the segment is mapped readable.
+    pub fn is_readable(&self) -> bool {
+        self.header.p_flags & PF_R != 0
+    }
+    /// Whether the segment is mapped writable.
+    pub fn is_writable(&self) -> bool {
+        self.header.p_flags & PF_W != 0
+    }
+    /// Whether the segment is mapped executable.
+    pub fn is_executable(&self) -> bool {
+        self.header.p_flags & PF_X != 0
+    }
+}
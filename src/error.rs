@@ -0,0 +1,182 @@
+//! The error type returned by fallible operations in this crate.
+use std::fmt;
+use std::io;
+
+use crate::context::Bits;
+
+/// An error raised by one of the crate's fallible APIs.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// An underlying IO operation failed.
+    Io(io::Error),
+    /// A value does not fit in the given word size.
+    ValueTooLarge(Bits),
+    /// A buffer is longer than the word it is unpacked into.
+    TooLong {
+        /// The length of the buffer.
+        len: usize,
+        /// The largest length which can be unpacked.
+        max: usize,
+    },
+    /// A buffer can't be split evenly into words.
+    Misaligned {
+        /// The length of the buffer.
+        len: usize,
+        /// The word size in bytes.
+        word_size: usize,
+    },
+    /// A leak contained no bytes.
+    EmptyLeak,
+    /// Values given to [`flat`](crate::flat()) overlap.
+    Overlap {
+        /// The offset of the first overlapping byte.
+        offset: usize,
+    },
+    /// A payload is longer than the length it should be padded to.
+    LengthExceeded {
+        /// The length of the payload.
+        len: usize,
+        /// The requested length.
+        length: usize,
+    },
+    /// A byte string used as an [`Offset`](crate::Offset) is not part of the cyclic pattern.
+    NotCyclic(Vec<u8>),
+    /// A negative [`Offset`](crate::Offset) was used without a payload length.
+    NoLength,
+    /// A negative [`Offset`](crate::Offset) points before the start of the payload.
+    OffsetBeforeStart {
+        /// The number of bytes back from the end.
+        back: usize,
+        /// The end of the payload.
+        end: usize,
+    },
+    /// A payload contains one of the [`context`](crate::context::set_bad_chars) bad characters.
+    BadChar {
+        /// The forbidden byte.
+        byte: u8,
+        /// The offset of the byte in the payload.
+        offset: usize,
+        /// The path to the item containing the byte within the value passed to
+        /// [`flat`](crate::flat()), such as `[2].1`, or empty if it isn't known.
+        item: String,
+    },
+    /// A string could not be decoded.
+    InvalidEncoding {
+        /// The encoding which was expected, such as `"hex"`.
+        encoding: &'static str,
+        /// The offset of the first invalid character.
+        offset: usize,
+    },
+    /// The name does not match any known architecture.
+    UnknownArch(String),
+    /// The name does not match any known operating system.
+    UnknownOs(String),
+    /// The bytes could not be parsed as an ELF file.
+    #[cfg(feature = "elf")]
+    Parse(goblin::error::Error),
+    /// The virtual address is not inside any loaded segment of the ELF.
+    #[cfg(feature = "elf")]
+    UnmappedAddress(usize),
+    /// The ELF does not contain a required section, segment or dynamic entry.
+    #[cfg(feature = "elf")]
+    Missing(&'static str),
+    /// A patch does not fit in the space available for it.
+    #[cfg(feature = "elf")]
+    NoSpace {
+        /// The number of bytes the patch needs.
+        needed: usize,
+        /// The number of bytes available.
+        available: usize,
+    },
+}
+
+/// A specialised `Result` type for this crate's [`Error`].
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "IO error: {}", e),
+            Error::ValueTooLarge(bits) => {
+                write!(f, "Value does not fit in {} bits", *bits as u8)
+            }
+            Error::TooLong { len, max } => {
+                write!(f, "Can't unpack {} bytes into a {} byte word", len, max)
+            }
+            Error::Misaligned { len, word_size } => write!(
+                f,
+                "{} bytes can't be split into {} byte words",
+                len, word_size
+            ),
+            Error::EmptyLeak => write!(f, "Leak was empty"),
+            Error::Overlap { offset } => {
+                write!(f, "Values in flat() overlap at offset {:#x}", offset)
+            }
+            Error::LengthExceeded { len, length } => write!(
+                f,
+                "Payload is {} bytes, longer than the requested {}",
+                len, length
+            ),
+            Error::NotCyclic(subsequence) => write!(
+                f,
+                "\"{}\" is not part of the cyclic pattern",
+                subsequence.escape_ascii()
+            ),
+            Error::NoLength => write!(f, "Negative offsets need a payload length"),
+            Error::OffsetBeforeStart { back, end } => write!(
+                f,
+                "Offset -{:#x} is before the start of a {:#x} byte payload",
+                back, end
+            ),
+            Error::BadChar { byte, offset, item } => {
+                write!(f, "Bad character {:#04x} at offset {:#x}", byte, offset)?;
+                if !item.is_empty() {
+                    write!(f, " in {}", item)?;
+                }
+                Ok(())
+            }
+            Error::InvalidEncoding { encoding, offset } => {
+                write!(f, "Invalid {} at offset {}", encoding, offset)
+            }
+            Error::UnknownArch(name) => write!(f, "Unknown architecture: {}", name),
+            Error::UnknownOs(name) => write!(f, "Unknown operating system: {}", name),
+            #[cfg(feature = "elf")]
+            Error::Parse(e) => write!(f, "Not a valid ELF file: {}", e),
+            #[cfg(feature = "elf")]
+            Error::UnmappedAddress(a) => write!(f, "Address {:#x} is not mapped", a),
+            #[cfg(feature = "elf")]
+            Error::Missing(what) => write!(f, "ELF has no {}", what),
+            #[cfg(feature = "elf")]
+            Error::NoSpace { needed, available } => write!(
+                f,
+                "Patch needs {} bytes but only {} are available",
+                needed, available
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            #[cfg(feature = "elf")]
+            Error::Parse(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+#[cfg(feature = "elf")]
+impl From<goblin::error::Error> for Error {
+    fn from(e: goblin::error::Error) -> Self {
+        Error::Parse(e)
+    }
+}
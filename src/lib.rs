@@ -10,12 +10,18 @@
 //! ```
 #![warn(missing_docs)]
 
+pub mod error;
+pub use error::Error;
+
 pub mod logging;
 pub use logging::*;
 
 pub mod context;
 pub use context::*;
 
+pub mod args;
+pub use args::{args, Args};
+
 #[cfg(feature = "tubes")]
 pub mod tubes;
 #[cfg(feature = "tubes")]
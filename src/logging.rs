@@ -1,14 +1,273 @@
 //! A `tracing_subscriber` log formatter
 //! [`init_logger`] **must** be called in order to use this!
+//!
+//! Along with tracing's [`debug`], [`info`], [`warn`] and [`error`], pwntools'
+//! [`success`](crate::success!), [`failure`](crate::failure!) and
+//! [`critical`](crate::critical!) messages are supported.
+//! ```
+//! use pwn::{context, failure, info, init_logger, success, Level};
+//! init_logger();
+//! info!("Leaking libc");
+//! success!("libc base: {:#x}", 0x7f0000000000u64);
+//! context::set_log_level(Level::WARN);
+//! failure!("This message is hidden");
+//! ```
+//! Messages are written to stderr. Long-running steps can show a live status line there with
+//! [`progress`], which messages are written above.
+//!
+//! Messages are filtered by the [`context`] log level of the thread which emits them, which can
+//! also be set with the `LOG_LEVEL`, `DEBUG` and `SILENT` [magic arguments](mod@crate::args).
 
-use colored::Colorize;
-pub use tracing::{debug, error, info, warn};
-use tracing::{Event, Level, Subscriber};
+use std::fmt::{self, Write as _};
+use std::fs::File;
+use std::io;
+use std::ops::Range;
+use std::path::Path;
+use std::sync::{Mutex, Once};
+
+use colored::{ColoredString, Colorize};
+#[doc(hidden)]
+pub use tracing as __tracing;
+use tracing::field::{Field, Visit};
+pub use tracing::{debug, error, info, warn, Level};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::filter::dynamic_filter_fn;
 use tracing_subscriber::fmt::format::Writer;
+use tracing_subscriber::fmt::time::{FormatTime, SystemTime};
 use tracing_subscriber::fmt::{FmtContext, FormatEvent, FormatFields};
+use tracing_subscriber::layer::SubscriberExt;
 use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::Layer;
+
+use crate::{args, context};
+
+mod progress;
+#[doc(hidden)]
+pub use progress::__redirect_terminal;
+pub use progress::{progress, Progress};
+
+// Event names used to tell pwntools' extra message kinds apart from plain tracing events
+#[doc(hidden)]
+pub const SUCCESS: &str = "pwn::success";
+#[doc(hidden)]
+pub const FAILURE: &str = "pwn::failure";
+#[doc(hidden)]
+pub const CRITICAL: &str = "pwn::critical";
+// Events whose `tones` field lists the parts of the message which `PwnFormatter` colours
+pub(crate) const TONED: &str = "pwn::toned";
+
+/// Log a successful step, prefixed with `[+]`, at the [`INFO`](Level::INFO) level.
+#[macro_export]
+macro_rules! success {
+    ($($arg:tt)+) => {
+        $crate::logging::__tracing::event!(
+            name: $crate::logging::SUCCESS,
+            $crate::Level::INFO,
+            $($arg)+
+        )
+    };
+}
+
+/// Log a failed step, prefixed with `[-]`, at the [`INFO`](Level::INFO) level.
+#[macro_export]
+macro_rules! failure {
+    ($($arg:tt)+) => {
+        $crate::logging::__tracing::event!(
+            name: $crate::logging::FAILURE,
+            $crate::Level::INFO,
+            $($arg)+
+        )
+    };
+}
+
+/// Log a fatal problem, prefixed with `[CRITICAL]`, at the [`ERROR`](Level::ERROR) level.
+#[macro_export]
+macro_rules! critical {
+    ($($arg:tt)+) => {
+        $crate::logging::__tracing::event!(
+            name: $crate::logging::CRITICAL,
+            $crate::Level::ERROR,
+            $($arg)+
+        )
+    };
+}
+
+/// Parse a log level, accepting pwntools' names (such as `critical` and `warning`) as well as
+/// tracing's.
+pub fn parse_level(s: &str) -> Option<Level> {
+    match s.to_ascii_lowercase().as_str() {
+        "critical" => Some(Level::ERROR),
+        "warning" => Some(Level::WARN),
+        "success" | "failure" => Some(Level::INFO),
+        other => other.parse().ok(),
+    }
+}
+
+/// How a value in a log message should be read, which decides its colour.
+#[derive(Copy, Clone, Debug)]
+pub(crate) enum Tone {
+    /// Coloured like `[+]` lines
+    Good,
+    /// Coloured like `[-]` lines
+    Bad,
+    /// Coloured like `[!]` lines
+    Partial,
+}
+
+impl Tone {
+    fn code(self) -> char {
+        match self {
+            Tone::Good => 'g',
+            Tone::Bad => 'b',
+            Tone::Partial => 'p',
+        }
+    }
+    fn from_code(code: char) -> Option<Self> {
+        [Tone::Good, Tone::Bad, Tone::Partial]
+            .into_iter()
+            .find(|tone| tone.code() == code)
+    }
+}
+
+fn tone(text: impl AsRef<str>, tone: Tone) -> ColoredString {
+    let text = text.as_ref();
+    match tone {
+        Tone::Good => text.green(),
+        Tone::Bad => text.red(),
+        Tone::Partial => text.yellow(),
+    }
+}
+
+// Encode the byte ranges of a message to colour, for the `tones` field of a `TONED` event
+#[cfg(feature = "elf")]
+pub(crate) fn encode_tones(spans: &[(Range<usize>, Tone)]) -> String {
+    spans
+        .iter()
+        .map(|(span, tone)| format!("{}-{}{}", span.start, span.end, tone.code()))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn decode_tones(tones: &str) -> Vec<(Range<usize>, Tone)> {
+    tones
+        .split(',')
+        .filter_map(|span| {
+            let (range, code) = span.split_at(span.len().checked_sub(1)?);
+            let (start, end) = range.split_once('-')?;
+            Some((
+                start.parse().ok()?..end.parse().ok()?,
+                Tone::from_code(code.chars().next()?)?,
+            ))
+        })
+        .collect()
+}
+
+// Escape control characters which terminals could interpret, as tracing does for fields
+fn escape_controls(s: &str) -> String {
+    s.chars()
+        .map(|c| match c {
+            '\x1b' | '\x07' | '\x08' | '\x0c' | '\x7f' => format!("\\x{:02x}", c as u32),
+            '\u{80}'..='\u{9f}' => format!("\\u{{{:x}}}", c as u32),
+            c => c.to_string(),
+        })
+        .collect()
+}
+
+// Remove ANSI escape sequences
+fn strip_ansi(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            // Skip the `[`, any parameters, and the final byte
+            chars.find(|c| ('@'..='~').contains(c) && *c != '[');
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+// Collects the message and tones of a `TONED` event
+#[derive(Default)]
+struct TonedFields {
+    message: String,
+    tones: String,
+}
+
+impl Visit for TonedFields {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        match field.name() {
+            "message" => self.message = format!("{:?}", value),
+            "tones" => self.tones = format!("{:?}", value),
+            _ => (),
+        }
+    }
+    fn record_str(&mut self, field: &Field, value: &str) {
+        match field.name() {
+            "message" => self.message = value.to_string(),
+            "tones" => self.tones = value.to_string(),
+            _ => (),
+        }
+    }
+}
+
+// Collects the fields of an event in the same layout as the default field formatter
+#[derive(Default)]
+struct PlainFields(String);
+
+impl Visit for PlainFields {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if !self.0.is_empty() {
+            self.0.push(' ');
+        }
+        let _ = match field.name() {
+            "message" => write!(self.0, "{:?}", value),
+            name => write!(self.0, "{}={:?}", name, value),
+        };
+    }
+    fn record_str(&mut self, field: &Field, value: &str) {
+        match field.name() {
+            "message" => self.record_debug(field, &format_args!("{}", value)),
+            _ => self.record_debug(field, &value),
+        }
+    }
+}
+
+struct PwnFormatter {
+    timestamps: bool,
+}
+
+impl PwnFormatter {
+    fn prefix(name: &str, level: Level) -> ColoredString {
+        match (name, level) {
+            (SUCCESS, _) => tone("+", Tone::Good).bold(),
+            (FAILURE, _) => tone("-", Tone::Bad).bold(),
+            (CRITICAL, _) => "CRITICAL".on_red().bold(),
+            (_, Level::TRACE) => "|".dimmed(),
+            (_, Level::DEBUG) => "|".purple(),
+            (_, Level::INFO) => "*".blue(),
+            (_, Level::WARN) => tone("!", Tone::Partial),
+            (_, Level::ERROR) => "ERROR".on_red(),
+        }
+    }
+
+    // The `[prefix] target: ` start of a line, which the progress status line shares
+    fn write_head(
+        writer: &mut impl fmt::Write,
+        prefix: &ColoredString,
+        target: &str,
+        ansi: bool,
+    ) -> fmt::Result {
+        if ansi {
+            write!(writer, "[{}] {}: ", prefix, target.green())
+        } else {
+            write!(writer, "[{}] {}: ", &**prefix, target)
+        }
+    }
+}
 
-struct PwnFormatter;
 impl<S, N> FormatEvent<S, N> for PwnFormatter
 where
     S: Subscriber + for<'a> LookupSpan<'a>,
@@ -19,23 +278,116 @@ where
         ctx: &FmtContext<'_, S, N>,
         mut writer: Writer<'_>,
         event: &Event<'_>,
-    ) -> std::fmt::Result {
+    ) -> fmt::Result {
         let meta = event.metadata();
-        let log_char = match *meta.level() {
-            Level::DEBUG => "|".purple(),
-            Level::INFO => "*".blue(),
-            Level::WARN => "!".yellow(),
-            _ => "X".red(),
-        };
-        write!(&mut writer, "[{}] {}: ", log_char, meta.target().green())?;
-        ctx.field_format().format_fields(writer.by_ref(), event)?;
+        if self.timestamps {
+            SystemTime.format_time(&mut writer)?;
+            write!(writer, " ")?;
+        }
+        let prefix = Self::prefix(meta.name(), *meta.level());
+        let ansi = writer.has_ansi_escapes();
+        Self::write_head(&mut writer, &prefix, meta.target(), ansi)?;
+        if meta.name() == TONED {
+            let mut fields = TonedFields::default();
+            event.record(&mut fields);
+            let message = &fields.message;
+            if writer.has_ansi_escapes() {
+                let mut written = 0;
+                for (span, colour) in decode_tones(&fields.tones) {
+                    let (Some(before), Some(part)) =
+                        (message.get(written..span.start), message.get(span.clone()))
+                    else {
+                        continue;
+                    };
+                    let part = escape_controls(part);
+                    write!(writer, "{}{}", escape_controls(before), tone(part, colour))?;
+                    written = span.end;
+                }
+                write!(writer, "{}", escape_controls(&message[written..]))?;
+            } else {
+                write!(writer, "{}", strip_ansi(message))?;
+            }
+        } else if writer.has_ansi_escapes() {
+            ctx.field_format().format_fields(writer.by_ref(), event)?;
+        } else {
+            let mut fields = PlainFields::default();
+            event.record(&mut fields);
+            write!(writer, "{}", strip_ansi(&fields.0))?;
+        }
         writeln!(writer)
     }
 }
 
-/// Initialise the logging subscriber
+static INIT: Once = Once::new();
+
+/// Initialise the logging subscriber. Events more verbose than the [`context`] log level of the
+/// thread which emits them are discarded.
+///
+/// If the `LOG_FILE` [magic argument](mod@crate::args) is given, messages are also appended to that
+/// file. Only the first call to this or [`init_logger_with_file`] has any effect.
+///
+/// The other magic arguments are applied to the context, as [`args::init`] does, so call this at
+/// the start of `main`, on the main thread.
 pub fn init_logger() {
-    tracing_subscriber::fmt()
-        .event_format(PwnFormatter {})
-        .init();
+    let file = args::args()
+        .value("LOG_FILE")
+        .and_then(|path| open_log(path).ok());
+    init(file);
+}
+
+/// Initialise the logging subscriber as [`init_logger`] does, additionally appending messages
+/// to the file at `path` with timestamps and without colours.
+///
+/// Returns an [`io::ErrorKind::AlreadyExists`] error if a subscriber was already installed, by an
+/// earlier call to this or [`init_logger`] or by the application, as the file can't be attached.
+pub fn init_logger_with_file(path: impl AsRef<Path>) -> io::Result<()> {
+    let already = || {
+        io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            "the logging subscriber is already initialised",
+        )
+    };
+    if INIT.is_completed() {
+        return Err(already());
+    }
+    if init(Some(open_log(path)?)) {
+        Ok(())
+    } else {
+        Err(already())
+    }
+}
+
+fn open_log(path: impl AsRef<Path>) -> io::Result<File> {
+    File::options().create(true).append(true).open(path)
+}
+
+// Returns whether this call installed the subscriber
+fn init(file: Option<File>) -> bool {
+    let mut installed = false;
+    INIT.call_once(|| {
+        let filter = || dynamic_filter_fn(|meta, _| *meta.level() <= context::get_log_level());
+        let mut layers = vec![tracing_subscriber::fmt::layer()
+            .with_writer(progress::Terminal)
+            .event_format(PwnFormatter { timestamps: false })
+            .with_filter(filter())
+            .boxed()];
+        if let Some(file) = file {
+            layers.push(
+                tracing_subscriber::fmt::layer()
+                    .with_writer(Mutex::new(file))
+                    .with_ansi(false)
+                    .event_format(PwnFormatter { timestamps: true })
+                    .with_filter(filter())
+                    .boxed(),
+            );
+        }
+        // Another subscriber may already have been installed by the application
+        installed = tracing_subscriber::registry()
+            .with(layers)
+            .try_init()
+            .is_ok();
+    });
+    // Apply the magic arguments once logging works, so that problems with them are shown
+    args::init();
+    installed
 }
@@ -0,0 +1,257 @@
+//! Live status lines for long-running steps.
+use std::fmt::Display;
+use std::io::{self, IsTerminal, Write};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::thread;
+use std::time::Duration;
+
+use tracing::Level;
+use tracing_subscriber::fmt::MakeWriter;
+
+use crate::logging::{escape_controls, PwnFormatter, FAILURE, SUCCESS};
+use crate::{args, context, debug, info};
+
+const FRAMES: [char; 4] = ['|', '/', '-', '\\'];
+const TICK: Duration = Duration::from_millis(100);
+const CLEAR: &str = "\r\x1b[2K";
+const TARGET: &str = "pwn::progress";
+
+struct Bar {
+    id: usize,
+    title: String,
+    status: String,
+}
+
+// Everything drawn on the terminal. Log lines and the status line are written with this locked,
+// so they can't interleave.
+struct Screen {
+    // The active progress indicators, drawn together on a single status line
+    bars: Vec<Bar>,
+    // Whether the status line is currently on screen
+    drawn: bool,
+    frame: usize,
+    // Whether the spinner thread is running
+    spinning: bool,
+    // Where to draw instead of stderr, which is then treated as a terminal
+    redirect: Option<Box<dyn Write + Send>>,
+}
+
+static SCREEN: Mutex<Screen> = Mutex::new(Screen {
+    bars: Vec::new(),
+    drawn: false,
+    frame: 0,
+    spinning: false,
+    redirect: None,
+});
+static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+fn screen() -> MutexGuard<'static, Screen> {
+    SCREEN.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+impl Screen {
+    fn is_terminal(&self) -> bool {
+        self.redirect.is_some() || io::stderr().is_terminal()
+    }
+    fn write(&mut self, buf: &[u8]) -> io::Result<()> {
+        match &mut self.redirect {
+            Some(out) => out.write_all(buf)?,
+            None => io::stderr().lock().write_all(buf)?,
+        }
+        Ok(())
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        match &mut self.redirect {
+            Some(out) => out.flush(),
+            None => io::stderr().flush(),
+        }
+    }
+    // Remove the status line from the screen
+    fn clear(&mut self) -> io::Result<()> {
+        if self.drawn {
+            self.write(CLEAR.as_bytes())?;
+            self.drawn = false;
+        }
+        Ok(())
+    }
+    // Clear the status line and draw it again, if there are any bars
+    fn draw(&mut self) -> io::Result<()> {
+        self.clear()?;
+        if !self.bars.is_empty() {
+            // Laid out like the log lines, with the spinner in place of the `*` prefix
+            let mut spinner = PwnFormatter::prefix("", Level::INFO);
+            spinner.input = FRAMES[self.frame % FRAMES.len()].to_string();
+            let mut line = String::new();
+            let _ = PwnFormatter::write_head(&mut line, &spinner, TARGET, true);
+            let bars = self
+                .bars
+                .iter()
+                .map(|bar| match bar.status.as_str() {
+                    "" => escape_controls(&bar.title),
+                    status => format!(
+                        "{}: {}",
+                        escape_controls(&bar.title),
+                        escape_controls(status)
+                    ),
+                })
+                .collect::<Vec<_>>();
+            line.push_str(&bars.join(" | "));
+            self.write(line.as_bytes())?;
+            self.drawn = true;
+        }
+        self.flush()
+    }
+}
+
+// Animate the status line until there are no bars left
+fn spin() {
+    loop {
+        thread::sleep(TICK);
+        let mut screen = screen();
+        if screen.bars.is_empty() {
+            let _ = screen.clear().and_then(|_| screen.flush());
+            screen.spinning = false;
+            return;
+        }
+        screen.frame += 1;
+        let _ = screen.draw();
+    }
+}
+
+/// Writes log lines to stderr, above the status line.
+pub(crate) struct Terminal;
+
+impl<'a> MakeWriter<'a> for Terminal {
+    type Writer = Terminal;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        Terminal
+    }
+}
+
+impl Write for Terminal {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut screen = screen();
+        let redraw = screen.drawn;
+        screen.clear()?;
+        screen.write(buf)?;
+        if redraw {
+            screen.draw()?;
+        }
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        screen().flush()
+    }
+}
+
+// Draw the status line and log lines to `out` instead of stderr, as if it were a terminal
+#[doc(hidden)]
+pub fn __redirect_terminal(out: impl Write + Send + 'static) {
+    screen().redirect = Some(Box::new(out));
+}
+
+/// A live status line, created by [`progress`].
+///
+/// When stderr is a terminal, a spinner is shown alongside the latest status until the
+/// `Progress` is finished with [`success`](Progress::success) or
+/// [`failure`](Progress::failure), which log a final `[+]` or `[-]` line. Otherwise, the start
+/// and end are logged as plain lines, and statuses at the [`DEBUG`](Level::DEBUG) level.
+///
+/// Any number of `Progress` indicators can be active at once, including from different threads.
+/// They share one status line, which log messages are written above.
+///
+/// A `Progress` dropped without being finished logs a success with the message `Done`.
+/// ```no_run
+/// use pwn::{init_logger, progress};
+/// init_logger();
+/// let p = progress("Leaking canary");
+/// let mut canary = vec![0u8];
+/// for i in 1..8 {
+///     p.status(format!("{:02x?}", canary));
+///     canary.push(i);
+/// }
+/// p.success(format!("{:02x?}", canary));
+/// ```
+pub struct Progress {
+    id: usize,
+    title: String,
+    live: bool,
+    finished: bool,
+}
+
+/// Start a new [`Progress`] indicator.
+pub fn progress(title: impl Into<String>) -> Progress {
+    let title = title.into();
+    let enabled = context::get_log_level() >= Level::INFO;
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    let noterm = args().flag("NOTERM");
+    let mut screen = screen();
+    let live = enabled && screen.is_terminal() && !noterm;
+    if live {
+        screen.bars.push(Bar {
+            id,
+            title: title.clone(),
+            status: String::new(),
+        });
+        let _ = screen.draw();
+        if !screen.spinning {
+            screen.spinning = true;
+            thread::spawn(spin);
+        }
+    } else {
+        drop(screen);
+        info!(target: TARGET, "{}", title);
+    }
+    Progress {
+        id,
+        title,
+        live,
+        finished: false,
+    }
+}
+
+impl Progress {
+    /// Update the status shown next to the title.
+    pub fn status(&self, status: impl Display) {
+        if self.live {
+            let mut screen = screen();
+            if let Some(bar) = screen.bars.iter_mut().find(|bar| bar.id == self.id) {
+                bar.status = status.to_string();
+            }
+        } else {
+            debug!(target: TARGET, "{}: {}", self.title, status);
+        }
+    }
+    /// Finish successfully, logging a `[+]` line with `message`.
+    pub fn success(mut self, message: impl Display) {
+        self.finish(true, &message)
+    }
+    /// Finish unsuccessfully, logging a `[-]` line with `message`.
+    pub fn failure(mut self, message: impl Display) {
+        self.finish(false, &message)
+    }
+
+    fn finish(&mut self, success: bool, message: &dyn Display) {
+        self.finished = true;
+        if self.live {
+            let mut screen = screen();
+            screen.bars.retain(|bar| bar.id != self.id);
+            let _ = screen.draw();
+        }
+        if success {
+            tracing::event!(name: SUCCESS, target: TARGET, Level::INFO, "{}: {}", self.title, message);
+        } else {
+            tracing::event!(name: FAILURE, target: TARGET, Level::INFO, "{}: {}", self.title, message);
+        }
+    }
+}
+
+impl Drop for Progress {
+    fn drop(&mut self) {
+        if !self.finished {
+            self.finish(true, &"Done")
+        }
+    }
+}
@@ -5,8 +5,10 @@ use std::io;
 use std::io::Write;
 
 extern crate crossbeam_utils;
+use crate::context;
 use crate::debug;
 use crate::tubes::buffer::Buffer;
+use crate::util::packing::check_bad_chars;
 use crossbeam_utils::thread;
 use std::time::Duration;
 
@@ -23,19 +25,20 @@ pub trait Tube {
     /// Retrieve all data from the `Tube`.
     ///
     /// * `timeout` - The maximum time to read for, defaults to 0.05s. If 0, clean only the
-    /// internal buffer.
+    ///   internal buffer.
     fn clean(&mut self, timeout: Duration) -> io::Result<Vec<u8>> {
         self.fill_buffer(Some(timeout))?;
         Ok(self.get_buffer().get(0))
     }
 
-    /// Receives from the `Tube`, returning once any data is available.
+    /// Receives from the `Tube`, returning once any data is available or the [`context`]
+    /// timeout expires.
     fn recv(&mut self) -> io::Result<Vec<u8>> {
-        self.recv_raw(None, None)
+        self.recv_raw(None, context::get_timeout())
     }
     /// Receives `n` bytes from the `Tube`.
     fn recvn(&mut self, n: usize) -> io::Result<Vec<u8>> {
-        self.recv_raw(Some(n), None)
+        self.recv_raw(Some(n), context::get_timeout())
     }
 
     #[doc(hidden)]
@@ -55,34 +58,55 @@ pub trait Tube {
         debug!("Sending {} bytes", data.len());
         self.send_raw(data)
     }
-    /// Appends a newline to the data before writing it to the `Tube`.
+    /// Appends the [`context`] newline to the data before writing it to the `Tube`.
     fn sendline<T: Into<Vec<u8>>>(&mut self, data: T) -> io::Result<()> {
         let mut data = data.into();
-        data.push(b'\n');
+        data.extend(context::get_newline());
         debug!("Sending {} bytes", data.len());
         self.send_raw(data)
     }
 
+    /// Writes data to the `Tube`, as [`send`](Tube::send) does, after checking that it doesn't
+    /// contain any of the [`context`] bad characters.
+    ///
+    /// If it does, nothing is sent, and an [`InvalidInput`](io::ErrorKind::InvalidInput) error
+    /// wrapping [`Error::BadChar`](crate::Error::BadChar) is returned.
+    fn send_checked<T: Into<Vec<u8>>>(&mut self, data: T) -> io::Result<()> {
+        let data = data.into();
+        check_bad_chars(&data).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        self.send(data)
+    }
+    /// Appends the [`context`] newline to the data before writing it to the `Tube`, as
+    /// [`sendline`](Tube::sendline) does, after checking that the data doesn't contain any of the
+    /// [`context`] bad characters. The newline itself isn't checked.
+    fn sendline_checked<T: Into<Vec<u8>>>(&mut self, data: T) -> io::Result<()> {
+        let data = data.into();
+        check_bad_chars(&data).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        self.sendline(data)
+    }
+
     #[doc(hidden)]
     fn send_raw(&mut self, data: Vec<u8>) -> io::Result<()>;
     /// Close both ends of the `Tube`.
     fn close(&mut self) -> io::Result<()>;
 
-    /// Receive until the given delimiter is received.
+    /// Receive until the given delimiter is received, including the delimiter. Each read waits
+    /// for at most the [`context`] timeout.
     fn recvuntil(&mut self, delim: &[u8]) -> io::Result<Vec<u8>> {
-        let mut pos;
         loop {
-            self.fill_buffer(Some(Duration::from_millis(50)))?;
-            pos = find_subsequence(self.get_buffer().data.make_contiguous(), delim);
+            let pos = find_subsequence(self.get_buffer().data.make_contiguous(), delim);
             if let Some(p) = pos {
-                return Ok(self.get_buffer().get(p + 1));
+                return Ok(self.get_buffer().get(p + delim.len()));
+            }
+            if self.fill_buffer(context::get_timeout())? == 0 {
+                return Err(io::ErrorKind::UnexpectedEof.into());
             }
         }
     }
 
-    /// Receive from the tube until a newline is received.
+    /// Receive from the tube until the [`context`] newline is received.
     fn recvline(&mut self) -> io::Result<Vec<u8>> {
-        self.recvuntil(b"\n")
+        self.recvuntil(&context::get_newline())
     }
     /// Get an interactive prompt for the connection. A second thread will print messages as they
     /// arrive.
@@ -91,16 +115,20 @@ pub trait Tube {
         Self: Clone + Send,
     {
         let mut receiver = self.clone();
+        let ctx = context::snapshot();
         // Make sure that the receiver thread does not outlive scope
         thread::scope(|s| {
-            s.spawn(|_| loop {
-                std::io::stdout()
-                    .write_all(
-                        &receiver
-                            .clean(Duration::from_millis(50))
-                            .unwrap_or_default(),
-                    )
-                    .expect("Couldn't write stdout")
+            s.spawn(move |_| {
+                ctx.apply();
+                loop {
+                    std::io::stdout()
+                        .write_all(
+                            &receiver
+                                .clean(Duration::from_millis(50))
+                                .unwrap_or_default(),
+                        )
+                        .expect("Couldn't write stdout")
+                }
             });
 
             let mut rl = Editor::<()>::new();
@@ -0,0 +1,372 @@
+use crate::context;
+use crate::util::packing::{p32, p32_signed, p64, p64_signed};
+
+/// The alphabet used by [`cyclic`] and [`cyclic_find`].
+pub const DEFAULT_ALPHABET: &[u8] = b"abcdefghijklmnopqrstuvwxyz";
+
+/// The subsequence to look up in a cyclic pattern, accepted by [`cyclic_find`] and
+/// [`cyclic_metasploit_find`].
+///
+/// Integers are packed with [`p32`] or [`p64`], using the [`context`] endianness, so a value
+/// read from a crashed register can be passed directly.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Subsequence(Vec<u8>);
+
+impl From<Vec<u8>> for Subsequence {
+    fn from(bytes: Vec<u8>) -> Self {
+        Self(bytes)
+    }
+}
+
+impl From<&[u8]> for Subsequence {
+    fn from(bytes: &[u8]) -> Self {
+        Self(bytes.to_vec())
+    }
+}
+
+impl<const N: usize> From<&[u8; N]> for Subsequence {
+    fn from(bytes: &[u8; N]) -> Self {
+        Self(bytes.to_vec())
+    }
+}
+
+impl From<&str> for Subsequence {
+    fn from(s: &str) -> Self {
+        Self(s.as_bytes().to_vec())
+    }
+}
+
+impl From<u32> for Subsequence {
+    fn from(v: u32) -> Self {
+        Self(p32(v))
+    }
+}
+
+impl From<u64> for Subsequence {
+    fn from(v: u64) -> Self {
+        Self(p64(v))
+    }
+}
+
+impl From<i32> for Subsequence {
+    fn from(v: i32) -> Self {
+        Self(p32_signed(v))
+    }
+}
+
+impl From<i64> for Subsequence {
+    fn from(v: i64) -> Self {
+        Self(p64_signed(v))
+    }
+}
+
+// The subsequence length matching the context word size
+fn default_n() -> usize {
+    context::get_bits() as usize / 8
+}
+
+/// An iterator over the de Bruijn sequence of `alphabet` with subsequences of length `n`, in
+/// which every possible subsequence appears exactly once.
+///
+/// The sequence is generated lazily, as the concatenation of the Lyndon words whose length
+/// divides `n`, in lexicographic order.
+pub struct DeBruijn<'a> {
+    alphabet: &'a [u8],
+    n: usize,
+    word: Vec<usize>,
+    emit: usize,
+    // The first `n - 1` symbols, which wrap around to close the cycle
+    tail: usize,
+}
+
+impl<'a> DeBruijn<'a> {
+    /// Create the sequence for `alphabet` and subsequences of length `n`.
+    ///
+    /// # Panics
+    ///
+    /// Panics when `alphabet` is empty or `n` is 0.
+    pub fn new(alphabet: &'a [u8], n: usize) -> Self {
+        assert!(!alphabet.is_empty(), "Alphabet must not be empty");
+        assert!(n > 0, "Subsequence length must be non-zero");
+        let mut sequence = Self {
+            alphabet,
+            n,
+            word: vec![0],
+            emit: 0,
+            tail: n - 1,
+        };
+        sequence.skip_unemitted();
+        sequence
+    }
+
+    // Advance to the next Lyndon word
+    fn advance(&mut self) {
+        let k = self.alphabet.len();
+        let m = self.word.len();
+        while self.word.len() < self.n {
+            self.word.push(self.word[self.word.len() - m]);
+        }
+        while self.word.last() == Some(&(k - 1)) {
+            self.word.pop();
+        }
+        if let Some(last) = self.word.last_mut() {
+            *last += 1;
+        }
+        self.emit = 0;
+    }
+
+    // Skip Lyndon words whose length does not divide `n`
+    fn skip_unemitted(&mut self) {
+        while !self.word.is_empty() && !self.n.is_multiple_of(self.word.len()) {
+            self.advance();
+        }
+    }
+}
+
+impl Iterator for DeBruijn<'_> {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<u8> {
+        if self.word.is_empty() {
+            // The sequence is cyclic, and starts with `n` copies of the first symbol
+            if self.tail == 0 {
+                return None;
+            }
+            self.tail -= 1;
+            return Some(self.alphabet[0]);
+        }
+        let symbol = self.alphabet[self.word[self.emit]];
+        self.emit += 1;
+        if self.emit == self.word.len() {
+            self.advance();
+            self.skip_unemitted();
+        }
+        Some(symbol)
+    }
+}
+
+/// Generate a cyclic pattern of `length` bytes, in which every subsequence the size of the
+/// [`context`] word is unique.
+/// ```
+/// use pwn::{context, cyclic, I386};
+/// context::set_arch(I386);
+/// assert_eq!(cyclic(20), b"aaaabaaacaaadaaaeaaa");
+/// ```
+///
+/// # Panics
+///
+/// Panics when `length` is longer than the longest possible pattern.
+pub fn cyclic(length: usize) -> Vec<u8> {
+    cyclic_with(length, DEFAULT_ALPHABET, default_n())
+}
+
+/// Generate a cyclic pattern of `length` bytes from `alphabet`, in which every subsequence of
+/// length `n` is unique.
+///
+/// # Panics
+///
+/// Panics when `length` is longer than the longest possible pattern, `alphabet` is empty or
+/// `n` is 0.
+pub fn cyclic_with(length: usize, alphabet: &[u8], n: usize) -> Vec<u8> {
+    let pattern: Vec<u8> = DeBruijn::new(alphabet, n).take(length).collect();
+    assert_eq!(
+        pattern.len(),
+        length,
+        "Can't create a pattern of length {} with {} symbols and subsequences of length {}",
+        length,
+        alphabet.len(),
+        n
+    );
+    pattern
+}
+
+/// Find the offset of `subsequence` in the pattern generated by [`cyclic`]. Only the first
+/// [`context`] word size bytes of `subsequence` are used, or all of it when it is shorter, in
+/// which case it is looked up in the pattern with subsequences of that length.
+/// ```
+/// use pwn::{context, cyclic, cyclic_find, AMD64, I386};
+/// context::set_arch(I386);
+/// assert_eq!(cyclic_find(b"faaa"), Some(20));
+/// // A little-endian register value of `0x61616167` is "gaaa" in memory
+/// assert_eq!(cyclic_find(0x61616167), Some(24));
+/// context::set_arch(AMD64);
+/// assert_eq!(cyclic_find(0x61616167u32), Some(24));
+/// ```
+pub fn cyclic_find(subsequence: impl Into<Subsequence>) -> Option<usize> {
+    let subsequence = subsequence.into();
+    let n = subsequence.0.len().clamp(1, default_n());
+    cyclic_find_with(subsequence, DEFAULT_ALPHABET, n)
+}
+
+/// Find the offset of `subsequence` in the pattern generated by [`cyclic_with`] with the same
+/// `alphabet` and `n`. Only the first `n` bytes of `subsequence` are used.
+///
+/// The offset is computed from the structure of the pattern rather than by searching it, so
+/// lookups are fast even when the pattern is far too long to generate.
+///
+/// Returns `None` if `subsequence` is shorter than `n` or contains bytes outside `alphabet`.
+pub fn cyclic_find_with(
+    subsequence: impl Into<Subsequence>,
+    alphabet: &[u8],
+    n: usize,
+) -> Option<usize> {
+    let Subsequence(bytes) = subsequence.into();
+    if n == 0 {
+        return None;
+    }
+    let window = bytes
+        .get(..n)?
+        .iter()
+        .map(|b| alphabet.iter().position(|symbol| symbol == b))
+        .collect::<Option<Vec<_>>>()?;
+    de_bruijn_offset(&window, alphabet.len())
+}
+
+// `word` rotated left by `by` symbols
+fn rotate(word: &[usize], by: usize) -> Vec<usize> {
+    [&word[by..], &word[..by]].concat()
+}
+
+// The offset of `window`, a word of symbol indices, in the sequence generated by `DeBruijn` for an
+// alphabet of `k` symbols.
+//
+// The sequence is made of the Lyndon roots of each necklace (the least rotation of a word) in
+// order, and the `n` symbols starting at the root of a necklace spell that necklace, except for
+// the last. A window either starts inside the root of its own necklace, or is a run of the last
+// symbol which ends a root followed by the start of the next necklace.
+fn de_bruijn_offset(window: &[usize], k: usize) -> Option<usize> {
+    let n = window.len();
+    let last = k - 1;
+    if k == 1 {
+        return Some(0);
+    }
+    let total = (k as u128).checked_pow(n as u32)?;
+    let run = window.iter().take_while(|&&symbol| symbol == last).count();
+    let offset = if run > 0 && window[run..].iter().all(|&symbol| symbol == 0) {
+        // The windows which wrap around the end of the cycle
+        total - run as u128
+    } else {
+        let necklace = (0..n).map(|by| rotate(window, by)).min()?;
+        let period = (1..=n)
+            .find(|&p| n.is_multiple_of(p) && necklace[p..] == necklace[..n - p])
+            .unwrap_or(n);
+        let start = (0..period).find(|&by| rotate(&necklace, by) == window)?;
+        // The next necklace only shares the symbols before the last one it can increment
+        let shared = necklace.iter().rposition(|&symbol| symbol != last)?;
+        if start + n - period <= shared {
+            preceding(&necklace, k) + start as u128
+        } else {
+            let mut next = window[run..].to_vec();
+            next.resize(n, 0);
+            preceding(&next, k) - run as u128
+        }
+    };
+    usize::try_from(offset).ok()
+}
+
+// The number of words with a rotation less than `word`, which is the offset of the root of the
+// first necklace not less than `word`
+fn preceding(word: &[usize], k: usize) -> u128 {
+    let n = word.len();
+    // The longest proper suffix of each prefix of `word` which is also a prefix, as in KMP
+    let mut border = vec![0; n + 1];
+    for i in 1..n {
+        let mut b = border[i];
+        while b > 0 && word[i] != word[b] {
+            b = border[b];
+        }
+        if word[i] == word[b] {
+            b += 1;
+        }
+        border[i + 1] = b;
+    }
+    // Reading a word one symbol at a time, the state is the longest suffix read so far which is a
+    // prefix of `word`. A transition is `None` when one of those suffixes falls below `word`.
+    let step: Vec<Vec<Option<usize>>> = (0..=n)
+        .map(|state| {
+            (0..k)
+                .map(|symbol| {
+                    let mut prefix = if state == n { border[n] } else { state };
+                    let mut matched = None;
+                    loop {
+                        if symbol < word[prefix] {
+                            return None;
+                        }
+                        if symbol == word[prefix] && matched.is_none() {
+                            matched = Some(prefix + 1);
+                        }
+                        if prefix == 0 {
+                            return Some(matched.unwrap_or(0));
+                        }
+                        prefix = border[prefix];
+                    }
+                })
+                .collect()
+        })
+        .collect();
+    // A word has no rotation less than `word` if it can be read twice in a row without a `None`
+    // transition. Count them by reading each word from the start state and, at the same time, from
+    // the state the first reading ends in.
+    let states = n + 1;
+    let mut not_less = 0;
+    for end in 0..states {
+        let mut counts = vec![0u128; states * states];
+        counts[end] = 1;
+        for _ in 0..n {
+            let mut next = vec![0u128; counts.len()];
+            for (pair, &count) in counts.iter().enumerate().filter(|(_, &count)| count > 0) {
+                let (first, second) = (pair / states, pair % states);
+                for pair in step[first].iter().zip(&step[second]) {
+                    if let (Some(first), Some(second)) = pair {
+                        next[first * states + second] += count;
+                    }
+                }
+            }
+            counts = next;
+        }
+        not_less += counts[end * states..(end + 1) * states]
+            .iter()
+            .sum::<u128>();
+    }
+    (k as u128).pow(n as u32) - not_less
+}
+
+/// Generate `length` bytes of the Metasploit pattern, `Aa0Aa1Aa2...`.
+///
+/// # Panics
+///
+/// Panics when `length` is longer than the pattern, which is 20280 bytes.
+pub fn cyclic_metasploit(length: usize) -> Vec<u8> {
+    let pattern: Vec<u8> = metasploit().take(length).collect();
+    assert_eq!(
+        pattern.len(),
+        length,
+        "The Metasploit pattern is only {} bytes long",
+        pattern.len()
+    );
+    pattern
+}
+
+/// Find the offset of `subsequence` in the pattern generated by [`cyclic_metasploit`].
+/// ```
+/// use pwn::{cyclic_metasploit, cyclic_metasploit_find};
+/// assert_eq!(cyclic_metasploit(12), b"Aa0Aa1Aa2Aa3");
+/// assert_eq!(cyclic_metasploit_find(b"1Aa2"), Some(5));
+/// ```
+pub fn cyclic_metasploit_find(subsequence: impl Into<Subsequence>) -> Option<usize> {
+    let Subsequence(needle) = subsequence.into();
+    if needle.is_empty() {
+        return None;
+    }
+    let pattern: Vec<u8> = metasploit().collect();
+    pattern
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+fn metasploit() -> impl Iterator<Item = u8> {
+    (b'A'..=b'Z').flat_map(|upper| {
+        (b'a'..=b'z')
+            .flat_map(move |lower| (b'0'..=b'9').flat_map(move |digit| [upper, lower, digit]))
+    })
+}
@@ -0,0 +1,389 @@
+use crate::context;
+use crate::error::Result;
+use crate::Error;
+
+const HEX: &[u8; 16] = b"0123456789abcdef";
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Encode `data` as lowercase hex.
+/// ```
+/// use pwn::enhex;
+/// assert_eq!(enhex(b"/bin/sh\0"), "2f62696e2f736800");
+/// ```
+pub fn enhex(data: impl AsRef<[u8]>) -> String {
+    let data = data.as_ref();
+    let mut out = String::with_capacity(data.len() * 2);
+    for byte in data {
+        out.push(HEX[(byte >> 4) as usize] as char);
+        out.push(HEX[(byte & 0xf) as usize] as char);
+    }
+    out
+}
+
+/// Decode hex, in either case. Surrounding whitespace is ignored, and an odd number of digits is
+/// treated as having a leading zero.
+///
+/// Returns an error if `s` contains anything other than hex digits.
+/// ```
+/// use pwn::unhex;
+/// assert_eq!(unhex("2F62696e").unwrap(), b"/bin");
+/// assert_eq!(unhex("abc").unwrap(), b"\x0a\xbc");
+/// assert!(unhex("xyz").is_err());
+/// ```
+pub fn unhex(s: impl AsRef<str>) -> Result<Vec<u8>> {
+    let s = s.as_ref().trim().as_bytes();
+    let digit = |i: usize| -> Result<u8> {
+        (s[i] as char)
+            .to_digit(16)
+            .map(|d| d as u8)
+            .ok_or(Error::InvalidEncoding {
+                encoding: "hex",
+                offset: i,
+            })
+    };
+    let mut out = Vec::with_capacity(s.len().div_ceil(2));
+    let mut i = 0;
+    if s.len() % 2 == 1 {
+        out.push(digit(0)?);
+        i = 1;
+    }
+    while i < s.len() {
+        out.push(digit(i)? << 4 | digit(i + 1)?);
+        i += 2;
+    }
+    Ok(out)
+}
+
+/// Encode `data` as padded base64, with the standard alphabet.
+/// ```
+/// use pwn::b64e;
+/// assert_eq!(b64e(b"pwn"), "cHdu");
+/// assert_eq!(b64e(b"pwnd"), "cHduZA==");
+/// ```
+pub fn b64e(data: impl AsRef<[u8]>) -> String {
+    let data = data.as_ref();
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let word = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |word, (i, b)| word | (*b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(BASE64[(word >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+/// Decode base64 with the standard alphabet. Padding is optional, and surrounding whitespace is
+/// ignored.
+///
+/// Returns an error if `s` contains characters outside the alphabet.
+/// ```
+/// use pwn::b64d;
+/// assert_eq!(b64d("cHduZA==").unwrap(), b"pwnd");
+/// assert_eq!(b64d("cHduZA").unwrap(), b"pwnd");
+/// assert!(b64d("cHd!").is_err());
+/// ```
+pub fn b64d(s: impl AsRef<str>) -> Result<Vec<u8>> {
+    let s = s.as_ref().trim().trim_end_matches('=').as_bytes();
+    let mut out = Vec::with_capacity(s.len() * 3 / 4);
+    for (c, chunk) in s.chunks(4).enumerate() {
+        let mut word = 0u32;
+        for (i, symbol) in chunk.iter().enumerate() {
+            let value = BASE64
+                .iter()
+                .position(|b| b == symbol)
+                .ok_or(Error::InvalidEncoding {
+                    encoding: "base64",
+                    offset: c * 4 + i,
+                })?;
+            word |= (value as u32) << (18 - 6 * i);
+        }
+        if chunk.len() == 1 {
+            // A single symbol doesn't encode a whole byte
+            return Err(Error::InvalidEncoding {
+                encoding: "base64",
+                offset: c * 4,
+            });
+        }
+        out.extend_from_slice(&word.to_be_bytes()[1..chunk.len()]);
+    }
+    Ok(out)
+}
+
+/// Percent-encode every byte of `data`, as pwntools does.
+/// ```
+/// use pwn::urlencode;
+/// assert_eq!(urlencode(b"a b"), "%61%20%62");
+/// ```
+pub fn urlencode(data: impl AsRef<[u8]>) -> String {
+    data.as_ref()
+        .iter()
+        .map(|b| format!("%{}", enhex([*b])))
+        .collect()
+}
+
+/// Decode percent-encoded `%xx` sequences. Other characters are kept as they are.
+///
+/// Returns an error if a `%` isn't followed by two hex digits.
+/// ```
+/// use pwn::urldecode;
+/// assert_eq!(urldecode("%2fbin%2Fsh").unwrap(), b"/bin/sh");
+/// assert!(urldecode("100%").is_err());
+/// ```
+pub fn urldecode(s: impl AsRef<str>) -> Result<Vec<u8>> {
+    let s = s.as_ref().as_bytes();
+    let mut out = Vec::with_capacity(s.len());
+    let mut i = 0;
+    while i < s.len() {
+        if s[i] == b'%' {
+            let byte = s
+                .get(i + 1..i + 3)
+                .and_then(|hex| std::str::from_utf8(hex).ok())
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                .ok_or(Error::InvalidEncoding {
+                    encoding: "URL encoding",
+                    offset: i,
+                })?;
+            out.push(byte);
+            i += 3;
+        } else {
+            out.push(s[i]);
+            i += 1;
+        }
+    }
+    Ok(out)
+}
+
+/// Xor any number of operands together. The result is as long as the longest operand, and
+/// shorter ones are repeated, so a short key can be applied to a long buffer.
+///
+/// Use [`xor!`](crate::xor!) to mix in integers, which are packed in a [`context`] sensitive
+/// way.
+/// ```
+/// use pwn::xor;
+/// assert_eq!(xor([&b"AAAA"[..], b"\x01\x02"]), b"@C@C");
+/// assert_eq!(xor([b"ab", b"ab", b"\x20\x20"]), b"  ");
+/// ```
+///
+/// # Panics
+///
+/// Panics when any of the operands is empty.
+pub fn xor<T: AsRef<[u8]>>(operands: impl IntoIterator<Item = T>) -> Vec<u8> {
+    let operands: Vec<T> = operands.into_iter().collect();
+    assert!(
+        operands.iter().all(|o| !o.as_ref().is_empty()),
+        "Can't xor with an empty operand"
+    );
+    let len = operands.iter().map(|o| o.as_ref().len()).max().unwrap_or(0);
+    (0..len)
+        .map(|i| {
+            operands.iter().fold(0, |acc, o| {
+                let o = o.as_ref();
+                acc ^ o[i % o.len()]
+            })
+        })
+        .collect()
+}
+
+/// Xor items of different types together, as [`xor`] does, after [flattening](crate::flat())
+/// them. The operands are not checked for the [`context`] bad characters, as they are usually
+/// what is being encoded to avoid them.
+///
+/// # Panics
+///
+/// Panics when an operand can't be flattened, or is empty.
+/// ```
+/// use pwn::{context, xor, I386};
+/// context::set_arch(I386);
+/// assert_eq!(xor!(b"AAAAAAAA", 0x01020304u32), b"EBC@EBC@");
+/// assert_eq!(xor!(b"AAAA", b' '), b"aaaa");
+/// ```
+#[macro_export]
+macro_rules! xor {
+    ($($item:expr),+ $(,)?) => {
+        $crate::xor([$(
+            $crate::FlatBuilder::new()
+                .bad_chars(::std::vec::Vec::new())
+                .flat(&$item)
+                .unwrap_or_else(|e| panic!("{}", e))
+        ),+])
+    };
+}
+
+/// Find a key of the [`context`] word size, such that neither the key nor `data` xored with it
+/// contain any of the [`context`] bad characters.
+///
+/// Returns the key and the xored data, or `None` if there is no such key.
+pub fn xor_key(data: impl AsRef<[u8]>) -> Option<(Vec<u8>, Vec<u8>)> {
+    xor_key_with(
+        data,
+        &context::get_bad_chars(),
+        context::get_bits() as usize / 8,
+    )
+}
+
+/// Find a key of `size` bytes, such that neither the key nor `data` xored with it contain any
+/// of `avoid`.
+///
+/// Returns the key and the xored data, or `None` if there is no such key.
+/// ```
+/// use pwn::{xor, xor_key_with};
+/// let (key, xored) = xor_key_with(b"/bin/sh\0", b"\0\n/", 4).unwrap();
+/// assert!(!key.iter().chain(&xored).any(|b| b"\0\n/".contains(b)));
+/// assert_eq!(xor([&xored, &key]), b"/bin/sh\0");
+/// ```
+///
+/// # Panics
+///
+/// Panics when `size` is 0.
+pub fn xor_key_with(
+    data: impl AsRef<[u8]>,
+    avoid: &[u8],
+    size: usize,
+) -> Option<(Vec<u8>, Vec<u8>)> {
+    assert!(size > 0, "Key size must be non-zero");
+    let data = data.as_ref();
+    // Each byte of the key only affects every `size`th byte of the data
+    let key = (0..size)
+        .map(|i| {
+            (0..=u8::MAX).find(|k| {
+                !avoid.contains(k)
+                    && data
+                        .iter()
+                        .skip(i)
+                        .step_by(size)
+                        .all(|b| !avoid.contains(&(b ^ k)))
+            })
+        })
+        .collect::<Option<Vec<u8>>>()?;
+    let xored = if data.is_empty() {
+        Vec::new()
+    } else {
+        xor([data, &key[..]])[..data.len()].to_vec()
+    };
+    Some((key, xored))
+}
+
+/// The bits of `data`, most significant first.
+/// ```
+/// use pwn::bits;
+/// assert_eq!(bits(b"\x81"), [true, false, false, false, false, false, false, true]);
+/// ```
+pub fn bits(data: impl AsRef<[u8]>) -> Vec<bool> {
+    data.as_ref()
+        .iter()
+        .flat_map(|b| (0..8).rev().map(move |i| b >> i & 1 == 1))
+        .collect()
+}
+
+/// The bits of `data` as a string of `0`s and `1`s, most significant first.
+/// ```
+/// use pwn::bits_str;
+/// assert_eq!(bits_str(b"A"), "01000001");
+/// ```
+pub fn bits_str(data: impl AsRef<[u8]>) -> String {
+    bits(data)
+        .into_iter()
+        .map(|b| if b { '1' } else { '0' })
+        .collect()
+}
+
+/// Pack bits, most significant first, into bytes. The last byte is padded with zeroes.
+/// ```
+/// use pwn::{bits, unbits};
+/// assert_eq!(unbits(bits(b"pwn")), b"pwn");
+/// assert_eq!(unbits([true, true]), b"\xc0");
+/// ```
+pub fn unbits(bits: impl IntoIterator<Item = bool>) -> Vec<u8> {
+    let bits: Vec<bool> = bits.into_iter().collect();
+    bits.chunks(8)
+        .map(|byte| {
+            byte.iter()
+                .enumerate()
+                .fold(0, |acc, (i, bit)| acc | (*bit as u8) << (7 - i))
+        })
+        .collect()
+}
+
+// The bits of `value` that fit in `width`
+fn mask(value: u128, width: u32) -> u128 {
+    assert!(
+        (1..=128).contains(&width),
+        "Width must be between 1 and 128 bits"
+    );
+    match width {
+        128 => value,
+        _ => value & ((1 << width) - 1),
+    }
+}
+
+/// Rotate `value` left by `k` bits, within the [`context`] word size.
+/// ```
+/// use pwn::{context, rol, I386};
+/// context::set_arch(I386);
+/// assert_eq!(rol(0x80000001, 4), 0x18);
+/// ```
+pub fn rol(value: u64, k: u32) -> u64 {
+    rol_with(value as u128, k, context::get_bits() as u32) as u64
+}
+
+/// Rotate `value` right by `k` bits, within the [`context`] word size.
+pub fn ror(value: u64, k: u32) -> u64 {
+    ror_with(value as u128, k, context::get_bits() as u32) as u64
+}
+
+/// Rotate `value` left by `k` bits, within `width` bits. Bits of `value` above `width` are
+/// ignored.
+/// ```
+/// use pwn::rol_with;
+/// assert_eq!(rol_with(0b1001, 1, 4), 0b0011);
+/// assert_eq!(rol_with(0x123456, 8, 24), 0x345612);
+/// ```
+///
+/// # Panics
+///
+/// Panics when `width` is 0 or more than 128.
+pub fn rol_with(value: u128, k: u32, width: u32) -> u128 {
+    let value = mask(value, width);
+    let k = k % width;
+    if k == 0 {
+        return value;
+    }
+    mask(value << k | value >> (width - k), width)
+}
+
+/// Rotate `value` right by `k` bits, within `width` bits. Bits of `value` above `width` are
+/// ignored.
+///
+/// # Panics
+///
+/// Panics when `width` is 0 or more than 128.
+pub fn ror_with(value: u128, k: u32, width: u32) -> u128 {
+    rol_with(value, width - k % width, width)
+}
+
+/// Reverse the order of the bits in each byte of `data`.
+/// ```
+/// use pwn::bitswap;
+/// assert_eq!(bitswap(b"\x01\x80\x0f"), b"\x80\x01\xf0");
+/// ```
+pub fn bitswap(data: impl AsRef<[u8]>) -> Vec<u8> {
+    data.as_ref().iter().map(|b| b.reverse_bits()).collect()
+}
+
+/// Reverse the order of the bits of `value`, within the [`context`] word size.
+/// ```
+/// use pwn::{bitswap_int, context, I386};
+/// context::set_arch(I386);
+/// assert_eq!(bitswap_int(1), 0x80000000);
+/// ```
+pub fn bitswap_int(value: u64) -> u64 {
+    let width = context::get_bits() as u32;
+    (mask(value as u128, width) as u64).reverse_bits() >> (64 - width)
+}
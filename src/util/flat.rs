@@ -1,4 +1,10 @@
-use crate::pack;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+use crate::context::{self, Bits, Endianness};
+use crate::error::Result;
+use crate::util::cyclic::{cyclic_find, DeBruijn, DEFAULT_ALPHABET};
+use crate::{check_bad_chars_with, pack, pack_signed, Error};
 use duplicate::duplicate_item;
 
 /// Flatten an item into bytes in a [`crate::context`]-aware way.
@@ -10,104 +16,692 @@ use duplicate::duplicate_item;
 /// context::set_arch(I386);
 /// assert_eq!(flat(&[1u32, 2, 3]), b"\x01\x00\x00\x00\x02\x00\x00\x00\x03\x00\x00\x00")
 /// ```
-/// Pack a complex sequence of items with offsets
+/// Mixing different types of items, with [`flat!`](crate::flat!) or a tuple
+/// ```
+/// use pwn::{flat, context, I386};
+/// context::set_arch(I386);
+/// let payload = flat!(b"AAAA", -1i32, String::from("BB"), vec![0x42u32]);
+/// assert_eq!(payload, b"AAAA\xff\xff\xff\xffBBB\x00\x00\x00");
+/// assert_eq!(flat((b"AAAA", -1i32, "BB", [0x42u32])), payload);
+/// ```
+/// Pack a complex sequence of items with [`Offset`]s
 /// ```
+/// use std::collections::BTreeMap;
 /// use pwn::{flat, context, I386, Flatten};
 /// context::set_arch(I386);
-/// let mapping: Vec<(usize, Box<dyn Flatten>)> = vec![
-///     (12, Box::new(0x41414141u32)),
-///     (24, Box::new(b"Hello!\xff"))
-/// ];
-///assert_eq!(flat(&*mapping), b"aaaaaaaaaaaaAAAAaaaaaaaaHello!\xff");
+/// let mapping: BTreeMap<usize, Box<dyn Flatten>> = BTreeMap::from([
+///     (12, Box::new(0x41414141u32) as _),
+///     (24, Box::new(b"Hello!\xff") as _),
+/// ]);
+/// assert_eq!(flat(mapping), b"aaaaaaaaaaaaAAAAaaaaaaaaHello!\xff");
+/// // Or a slice of pairs
+/// let pairs: Vec<(usize, Box<dyn Flatten>)> = vec![(12, Box::new(0x41414141u32))];
+/// assert_eq!(flat(&*pairs), b"aaaaaaaaaaaaAAAA");
+/// // Keys can also be subsequences of the cyclic pattern
+/// assert_eq!(flat! { b"caaa" => 0x41414141u32, 0 => b"!" }, b"!aaaaaaaAAAA");
 /// ```
 /// # Warnings
 /// * Sequences of `u8` will *not* be packed according to the context, and instead returned as passed.
-/// This is to allow byte-strings to be included in `flat()`.
-/// * Overlapping values will result in a panic.
+///   This is to allow byte-strings to be included in `flat()`. Every other integer type is packed
+///   to the context word size, with signed types packed as signed.
+/// * `Box<dyn Flatten>` does not itself implement [`Flatten`], so it can't be passed alone or nested
+///   in a tuple or `Option`. It is only flattened as an item of a slice, array or `Vec`, or as a
+///   value of an offset map or of `(offset, Box<dyn Flatten>)` pairs. Implementing it would make
+///   those pairs tuples as well, so they could no longer be placed at their offsets.
+/// * Overlapping values, integers too large for the word size, or any of the [`context`] bad
+///   characters will result in a panic. Use [`FlatBuilder`] to get an error instead.
 pub fn flat(item: impl Flatten) -> Vec<u8> {
     item.flatten()
 }
 
 /// An object that can be 'flattened' down to a byte buffer.
 pub trait Flatten {
-    /// Return the 'flattened' form of `self`.
-    fn flatten(&self) -> Vec<u8>;
+    /// Return the 'flattened' form of `self`, filling gaps with `'a'`.
+    ///
+    /// # Panics
+    ///
+    /// Panics when [`try_flatten`](Flatten::try_flatten) fails.
+    fn flatten(&self) -> Vec<u8> {
+        self.try_flatten(&Filler::default())
+            .and_then(check_payload)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+    /// Return the 'flattened' form of `self`, filling any gaps with `filler`.
+    fn try_flatten(&self, filler: &Filler) -> Result<Vec<u8>>;
+    /// Return the 'flattened' form of `self`, padded to `length` bytes with `filler`.
+    ///
+    /// Offset-keyed maps count negative [`Offset`]s back from `length`.
+    fn try_flatten_padded(&self, filler: &Filler, length: usize) -> Result<Vec<u8>> {
+        pad(self.try_flatten(filler)?, filler, length)
+    }
+}
+
+fn pad(mut out: Vec<u8>, filler: &Filler, length: usize) -> Result<Vec<u8>> {
+    if out.len() > length {
+        return Err(Error::LengthExceeded {
+            len: out.len(),
+            length,
+        });
+    }
+    let start = out.len();
+    let padding = filler.fill(length);
+    check_filler(&padding[start..], start)?;
+    out.extend_from_slice(&padding[start..]);
+    Ok(out)
+}
+
+// Check a whole payload, for items which aren't inside a container
+fn check_payload(out: Vec<u8>) -> Result<Vec<u8>> {
+    check_bad_chars_with(&out, &context::get_bad_chars())?;
+    Ok(out)
+}
+
+// Check filler bytes which start at `start`
+fn check_filler(fill: &[u8], start: usize) -> Result<()> {
+    check_bad_chars_with(fill, &context::get_bad_chars())
+        .map_err(|e| nest_bad_char(e, || "filler".to_string(), start))
+}
+
+// Report a bad character found in an item of a container with its position in the container
+fn nest_bad_char(e: Error, path: impl FnOnce() -> String, start: usize) -> Error {
+    match e {
+        Error::BadChar { byte, offset, item } => Error::BadChar {
+            byte,
+            offset: start + offset,
+            item: path() + &item,
+        },
+        e => e,
+    }
+}
+
+// Flatten an item of a container which starts at `start`, checking it for bad characters
+fn flatten_item<T: Flatten + ?Sized>(
+    item: &T,
+    filler: &Filler,
+    bad_chars: &[u8],
+    path: impl Fn() -> String,
+    start: usize,
+) -> Result<Vec<u8>> {
+    let flattened = item
+        .try_flatten(filler)
+        .and_then(|flattened| {
+            check_bad_chars_with(&flattened, bad_chars)?;
+            Ok(flattened)
+        })
+        .map_err(|e| nest_bad_char(e, &path, start))?;
+    Ok(flattened)
+}
+
+// Concatenates the items of a sequence
+struct Items<'a> {
+    filler: &'a Filler,
+    bad_chars: Vec<u8>,
+    out: Vec<u8>,
+}
+
+impl<'a> Items<'a> {
+    fn new(filler: &'a Filler) -> Self {
+        Self {
+            filler,
+            bad_chars: context::get_bad_chars(),
+            out: Vec::new(),
+        }
+    }
+
+    fn push<T: Flatten + ?Sized>(&mut self, item: &T, path: impl Fn() -> String) -> Result<()> {
+        let start = self.out.len();
+        let flattened = flatten_item(item, self.filler, &self.bad_chars, path, start)?;
+        self.out.extend(flattened);
+        Ok(())
+    }
+}
+
+/// The bytes used to fill the gaps in a payload, and to pad it to a [`FlatBuilder::length`].
+///
+/// The filler is indexed by offset, so the byte at a given offset of the payload is the same
+/// however the gap around it came about.
+#[derive(Clone)]
+pub struct Filler(FillerKind);
+
+#[derive(Clone)]
+enum FillerKind {
+    Byte(u8),
+    Cyclic,
+    Iter(Rc<dyn Fn() -> Box<dyn Iterator<Item = u8>>>),
+}
+
+impl Filler {
+    /// Fill with a single repeated byte.
+    pub fn byte(byte: u8) -> Self {
+        Self(FillerKind::Byte(byte))
+    }
+    /// Fill with the [`cyclic`](crate::cyclic()) pattern for the [`context`] word size, so that
+    /// the offset of any filler which ends up in a register can be found with
+    /// [`cyclic_find`].
+    pub fn cyclic() -> Self {
+        Self(FillerKind::Cyclic)
+    }
+    /// Fill with the bytes of `iter`, repeating it if needed.
+    ///
+    /// # Panics
+    ///
+    /// Panics when `iter` is empty.
+    pub fn iter<I>(iter: I) -> Self
+    where
+        I: IntoIterator<Item = u8>,
+        I::IntoIter: Clone + 'static,
+    {
+        let iter = iter.into_iter();
+        assert!(iter.clone().next().is_some(), "Filler must not be empty");
+        Self(FillerKind::Iter(Rc::new(move || {
+            Box::new(iter.clone().cycle())
+        })))
+    }
+    /// The first `len` bytes of the filler.
+    pub fn fill(&self, len: usize) -> Vec<u8> {
+        match &self.0 {
+            FillerKind::Byte(byte) => vec![*byte; len],
+            FillerKind::Cyclic => {
+                let n = context::get_bits() as usize / 8;
+                let mut pattern: Vec<u8> = DeBruijn::new(DEFAULT_ALPHABET, n).take(len).collect();
+                // The sequence is cyclic, so it can be repeated past its end
+                let period = DEFAULT_ALPHABET.len().pow(n as u32);
+                while pattern.len() < len {
+                    pattern.push(pattern[pattern.len() - period]);
+                }
+                pattern
+            }
+            FillerKind::Iter(iter) => iter().take(len).collect(),
+        }
+    }
+}
+
+impl Default for Filler {
+    /// Fill with `'a'`.
+    fn default() -> Self {
+        Self::byte(b'a')
+    }
+}
+
+impl From<u8> for Filler {
+    fn from(byte: u8) -> Self {
+        Self::byte(byte)
+    }
+}
+
+impl std::fmt::Debug for Filler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.0 {
+            FillerKind::Byte(byte) => f.debug_tuple("Byte").field(byte).finish(),
+            FillerKind::Cyclic => f.write_str("Cyclic"),
+            FillerKind::Iter(_) => f.write_str("Iter"),
+        }
+    }
+}
+
+/// Configurable flattening, which returns an error instead of panicking.
+/// ```
+/// use std::collections::BTreeMap;
+/// use pwn::{context, Bits, Endianness, FlatBuilder, Filler, Flatten, I386};
+/// context::set_arch(I386);
+/// let payload = FlatBuilder::new()
+///     .filler(Filler::cyclic())
+///     .length(16)
+///     .flat(BTreeMap::from([(8usize, Box::new(0x42424242u32) as Box<dyn Flatten>)]))
+///     .unwrap();
+/// assert_eq!(payload, b"aaaabaaaBBBBdaaa");
+///
+/// let header = FlatBuilder::new()
+///     .bits(Bits::Sixteen)
+///     .endian(Endianness::Big)
+///     .flat((0x1234u16, 2u16))
+///     .unwrap();
+/// assert_eq!(header, b"\x12\x34\x00\x02");
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct FlatBuilder {
+    filler: Filler,
+    length: Option<usize>,
+    bits: Option<Bits>,
+    endian: Option<Endianness>,
+    bad_chars: Option<Vec<u8>>,
+}
+
+impl FlatBuilder {
+    /// The default settings: fill with `'a'`, with no padding, using the [`context`] word size
+    /// and endianness.
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Fill gaps, and the padding up to the [`length`](FlatBuilder::length), with `filler`.
+    pub fn filler(mut self, filler: impl Into<Filler>) -> Self {
+        self.filler = filler.into();
+        self
+    }
+    /// Pad the payload to `length` bytes with the filler. Longer payloads are an error.
+    pub fn length(mut self, length: usize) -> Self {
+        self.length = Some(length);
+        self
+    }
+    /// Pack integers to `bits` instead of the context word size.
+    pub fn bits(mut self, bits: Bits) -> Self {
+        self.bits = Some(bits);
+        self
+    }
+    /// Pack integers with `endian` instead of the context endianness.
+    pub fn endian(mut self, endian: Endianness) -> Self {
+        self.endian = Some(endian);
+        self
+    }
+    /// Reject payloads containing any of `bad_chars`, instead of the [`context`] bad characters.
+    pub fn bad_chars(mut self, bad_chars: impl Into<Vec<u8>>) -> Self {
+        self.bad_chars = Some(bad_chars.into());
+        self
+    }
+    /// Flatten `item` with these settings.
+    ///
+    /// Returns an error if values overlap, an integer doesn't fit in the word size, the
+    /// payload is longer than the [`length`](FlatBuilder::length), or a bad character is found.
+    /// Bad characters are reported with their offset, and the path to the item which contains
+    /// them.
+    /// ```
+    /// use pwn::{context, Error, FlatBuilder, I386};
+    /// context::set_arch(I386);
+    /// let err = FlatBuilder::new()
+    ///     .bad_chars(b"\n")
+    ///     .flat((b"AAAA", [0x401000u32, 0x40100au32]))
+    ///     .unwrap_err();
+    /// assert_eq!(err.to_string(), "Bad character 0x0a at offset 0x8 in .1[1]");
+    /// ```
+    pub fn flat(&self, item: impl Flatten) -> Result<Vec<u8>> {
+        let guard = context::push();
+        if let Some(bits) = self.bits {
+            guard.bits(bits);
+        }
+        if let Some(endian) = self.endian {
+            guard.endian(endian);
+        }
+        if let Some(bad_chars) = &self.bad_chars {
+            guard.bad_chars(bad_chars.clone());
+        }
+        match self.length {
+            Some(length) => item.try_flatten_padded(&self.filler, length),
+            None => item.try_flatten(&self.filler),
+        }
+        .and_then(check_payload)
+    }
+}
+
+/// Flatten a list of items of different types, as [`flat`] does, without boxing each one.
+/// ```
+/// use pwn::{flat, context, p64, AMD64};
+/// context::set_arch(AMD64);
+/// let rop = flat!(b"A".repeat(40), 0x401234u64, p64(0xdeadbeef), "/bin/sh\0");
+/// assert_eq!(rop.len(), 40 + 8 + 8 + 8);
+/// ```
+/// Items can also be placed at [`Offset`]s, as with [`offsets!`](crate::offsets!).
+/// ```
+/// use pwn::{flat, context, AMD64};
+/// context::set_arch(AMD64);
+/// let rop = flat! {
+///     b"kaaaaaaa" => 0x401234u64,
+///     0 => b"/bin/sh\0",
+/// };
+/// assert_eq!(&rop[..16], b"/bin/sh\0aaaaaaaa");
+/// assert_eq!(&rop[80..], b"\x34\x12\x40\x00\x00\x00\x00\x00");
+/// ```
+#[macro_export]
+macro_rules! flat {
+    ($($key:expr => $value:expr),+ $(,)?) => {
+        $crate::flat($crate::offsets! { $($key => $value),+ })
+    };
+    ($($item:expr),* $(,)?) => {
+        $crate::flat(&[$(&$item as &dyn $crate::Flatten),*] as &[&dyn $crate::Flatten])
+    };
+}
+
+/// Build a map of [`Offset`]s to items of different types, to be flattened by [`flat`] or a
+/// [`FlatBuilder`].
+/// ```
+/// use pwn::{context, offsets, FlatBuilder, I386};
+/// context::set_arch(I386);
+/// let payload = FlatBuilder::new()
+///     .length(16)
+///     .flat(offsets! {
+///         b"baaa" => 0xdeadbeefu32,
+///         -2 => b"\r\n",
+///     })
+///     .unwrap();
+/// assert_eq!(payload, b"aaaa\xef\xbe\xad\xdeaaaaaa\r\n");
+/// ```
+#[macro_export]
+macro_rules! offsets {
+    ($($key:expr => $value:expr),* $(,)?) => {
+        [$((
+            $crate::Offset::from($key),
+            ::std::boxed::Box::new($value) as ::std::boxed::Box<dyn $crate::Flatten + '_>,
+        )),*]
+        .into_iter()
+        .collect::<::std::collections::BTreeMap<
+            $crate::Offset,
+            ::std::boxed::Box<dyn $crate::Flatten + '_>,
+        >>()
+    };
 }
+
+impl<T> Flatten for &T
+where
+    T: Flatten + ?Sized,
+{
+    fn try_flatten(&self, filler: &Filler) -> Result<Vec<u8>> {
+        (**self).try_flatten(filler)
+    }
+    fn try_flatten_padded(&self, filler: &Filler, length: usize) -> Result<Vec<u8>> {
+        (**self).try_flatten_padded(filler, length)
+    }
+}
+
+// Only sized boxes are covered. `Box<dyn Flatten>` is deliberately not `Flatten`, and is instead
+// flattened inside slices, so that a slice of `(offset, Box<dyn Flatten>)` pairs places each value
+// at its offset. Otherwise those pairs would also be tuples, and the impls would conflict.
+impl<T> Flatten for Box<T>
+where
+    T: Flatten,
+{
+    fn try_flatten(&self, filler: &Filler) -> Result<Vec<u8>> {
+        (**self).try_flatten(filler)
+    }
+    fn try_flatten_padded(&self, filler: &Filler, length: usize) -> Result<Vec<u8>> {
+        (**self).try_flatten_padded(filler, length)
+    }
+}
+
 #[duplicate_item(
     int_type;
     [u16];
     [u32];
     [u64];
+    [usize];
 )]
 impl Flatten for int_type {
-    fn flatten(&self) -> Vec<u8> {
-        pack(*self).expect("Value was too large to be flattened")
+    fn try_flatten(&self, _: &Filler) -> Result<Vec<u8>> {
+        pack(*self).ok_or_else(|| Error::ValueTooLarge(context::get_bits()))
     }
 }
 
-impl Flatten for &str {
-    fn flatten(&self) -> Vec<u8> {
-        self.bytes().collect()
+#[duplicate_item(
+    int_type;
+    [i8];
+    [i16];
+    [i32];
+    [i64];
+    [isize];
+)]
+impl Flatten for int_type {
+    fn try_flatten(&self, _: &Filler) -> Result<Vec<u8>> {
+        pack_signed(*self).ok_or_else(|| Error::ValueTooLarge(context::get_bits()))
+    }
+}
+
+impl Flatten for str {
+    fn try_flatten(&self, _: &Filler) -> Result<Vec<u8>> {
+        Ok(self.bytes().collect())
+    }
+}
+
+impl Flatten for String {
+    fn try_flatten(&self, filler: &Filler) -> Result<Vec<u8>> {
+        self.as_str().try_flatten(filler)
     }
 }
 
 // Special case - already bytes, don't pack
 impl Flatten for u8 {
-    fn flatten(&self) -> Vec<u8> {
-        vec![*self]
+    fn try_flatten(&self, _: &Filler) -> Result<Vec<u8>> {
+        Ok(vec![*self])
     }
 }
 
-impl<T> Flatten for &[T]
+impl<T> Flatten for [T]
 where
     T: Flatten,
 {
-    fn flatten(&self) -> Vec<u8> {
-        self.iter()
-            .map(|v| v.flatten())
-            .collect::<Vec<Vec<_>>>()
-            .concat()
+    fn try_flatten(&self, filler: &Filler) -> Result<Vec<u8>> {
+        let mut items = Items::new(filler);
+        for (i, v) in self.iter().enumerate() {
+            items.push(v, || format!("[{}]", i))?;
+        }
+        Ok(items.out)
+    }
+}
+
+impl<T, const N: usize> Flatten for [T; N]
+where
+    [T]: Flatten,
+{
+    fn try_flatten(&self, filler: &Filler) -> Result<Vec<u8>> {
+        self.as_slice().try_flatten(filler)
+    }
+    fn try_flatten_padded(&self, filler: &Filler, length: usize) -> Result<Vec<u8>> {
+        self.as_slice().try_flatten_padded(filler, length)
+    }
+}
+
+impl Flatten for [Box<dyn Flatten + '_>] {
+    fn try_flatten(&self, filler: &Filler) -> Result<Vec<u8>> {
+        let mut items = Items::new(filler);
+        for (i, v) in self.iter().enumerate() {
+            items.push(&**v, || format!("[{}]", i))?;
+        }
+        Ok(items.out)
+    }
+}
+
+impl<T> Flatten for Vec<T>
+where
+    [T]: Flatten,
+{
+    fn try_flatten(&self, filler: &Filler) -> Result<Vec<u8>> {
+        self.as_slice().try_flatten(filler)
+    }
+    fn try_flatten_padded(&self, filler: &Filler, length: usize) -> Result<Vec<u8>> {
+        self.as_slice().try_flatten_padded(filler, length)
     }
 }
 
-impl<T, const N: usize> Flatten for &[T; N]
+// `None` is left out entirely
+impl<T> Flatten for Option<T>
 where
     T: Flatten,
 {
-    fn flatten(&self) -> Vec<u8> {
-        self.as_slice().flatten()
+    fn try_flatten(&self, filler: &Filler) -> Result<Vec<u8>> {
+        self.as_ref()
+            .map_or_else(|| Ok(Vec::new()), |v| v.try_flatten(filler))
     }
 }
 
-impl<I> Flatten for &[(I, Box<dyn Flatten>)]
+macro_rules! impl_flatten_tuple {
+    ($($index:tt $name:ident),+) => {
+        impl<$($name: Flatten),+> Flatten for ($($name,)+) {
+            #[allow(non_snake_case)]
+            fn try_flatten(&self, filler: &Filler) -> Result<Vec<u8>> {
+                let ($($name,)+) = self;
+                let mut items = Items::new(filler);
+                $(items.push($name, || format!(".{}", $index))?;)+
+                Ok(items.out)
+            }
+        }
+    };
+}
+
+impl_flatten_tuple!(0 A);
+impl_flatten_tuple!(0 A, 1 B);
+impl_flatten_tuple!(0 A, 1 B, 2 C);
+impl_flatten_tuple!(0 A, 1 B, 2 C, 3 D);
+impl_flatten_tuple!(0 A, 1 B, 2 C, 3 D, 4 E);
+impl_flatten_tuple!(0 A, 1 B, 2 C, 3 D, 4 E, 5 F);
+impl_flatten_tuple!(0 A, 1 B, 2 C, 3 D, 4 E, 5 F, 6 G);
+impl_flatten_tuple!(0 A, 1 B, 2 C, 3 D, 4 E, 5 F, 6 G, 7 H);
+impl_flatten_tuple!(0 A, 1 B, 2 C, 3 D, 4 E, 5 F, 6 G, 7 H, 8 I);
+impl_flatten_tuple!(0 A, 1 B, 2 C, 3 D, 4 E, 5 F, 6 G, 7 H, 8 I, 9 J);
+impl_flatten_tuple!(0 A, 1 B, 2 C, 3 D, 4 E, 5 F, 6 G, 7 H, 8 I, 9 J, 10 K);
+impl_flatten_tuple!(0 A, 1 B, 2 C, 3 D, 4 E, 5 F, 6 G, 7 H, 8 I, 9 J, 10 K, 11 L);
+
+/// The position of an item in an offset-keyed map passed to [`flat`].
+///
+/// Integers are converted to offsets from the start, or from the end if they're negative. Byte
+/// strings are converted to the offset of that subsequence in the [`cyclic`](crate::cyclic())
+/// pattern, so that values can be placed where a crash shows the pattern was. As with
+/// [`cyclic_find`], subsequences shorter than the [`context`] word size, such as the 4 bytes of
+/// a 32-bit register, are found in the pattern with subsequences of their own length.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Offset {
+    /// A number of bytes from the start of the payload.
+    Start(usize),
+    /// A number of bytes back from the end of the payload, which must be given a
+    /// [`length`](FlatBuilder::length).
+    End(usize),
+    /// The offset of a subsequence of the cyclic pattern.
+    Cyclic(Vec<u8>),
+}
+
+#[duplicate_item(
+    int_type;
+    [u8];
+    [u16];
+    [u32];
+    [u64];
+    [usize];
+)]
+impl From<int_type> for Offset {
+    #[allow(clippy::unnecessary_cast)]
+    fn from(offset: int_type) -> Self {
+        Offset::Start(offset as usize)
+    }
+}
+
+#[duplicate_item(
+    int_type;
+    [i8];
+    [i16];
+    [i32];
+    [i64];
+    [isize];
+)]
+impl From<int_type> for Offset {
+    #[allow(clippy::unnecessary_cast)]
+    fn from(offset: int_type) -> Self {
+        match offset {
+            o if o < 0 => Offset::End(o.unsigned_abs() as usize),
+            o => Offset::Start(o as usize),
+        }
+    }
+}
+
+impl From<&[u8]> for Offset {
+    fn from(subsequence: &[u8]) -> Self {
+        Offset::Cyclic(subsequence.to_vec())
+    }
+}
+
+impl<const N: usize> From<&[u8; N]> for Offset {
+    fn from(subsequence: &[u8; N]) -> Self {
+        Offset::Cyclic(subsequence.to_vec())
+    }
+}
+
+impl From<Vec<u8>> for Offset {
+    fn from(subsequence: Vec<u8>) -> Self {
+        Offset::Cyclic(subsequence)
+    }
+}
+
+impl From<&str> for Offset {
+    fn from(subsequence: &str) -> Self {
+        Offset::Cyclic(subsequence.as_bytes().to_vec())
+    }
+}
+
+impl<I> Flatten for BTreeMap<I, Box<dyn Flatten + '_>>
 where
-    usize: From<I>,
-    I: Copy,
+    I: Clone + Into<Offset>,
 {
-    fn flatten(&self) -> Vec<u8> {
-        let mut map: Vec<(usize, &Box<dyn Flatten>)> =
-            self.iter().map(|(i, f)| ((*i).into(), f)).collect();
-        map.sort_by(|(a, _), (b, _)| a.partial_cmp(b).unwrap());
-        // Prepare a vec with near the right capacity
-        let mut out: Vec<Option<u8>> = Vec::with_capacity(map.first().map_or(0, |(i, _)| *i));
-        for (i, val) in map {
-            let flattened = val.flatten();
-
-            let end_i = i + flattened.len();
-            if end_i > out.len() {
-                out.extend(std::iter::repeat(None).take(end_i - out.len()));
+    fn try_flatten(&self, filler: &Filler) -> Result<Vec<u8>> {
+        flatten_offsets(self, filler, None)
+    }
+    fn try_flatten_padded(&self, filler: &Filler, length: usize) -> Result<Vec<u8>> {
+        pad(flatten_offsets(self, filler, Some(length))?, filler, length)
+    }
+}
+
+// Pairs of offsets and values are placed as in a map, with duplicate offsets overlapping
+impl<I> Flatten for [(I, Box<dyn Flatten + '_>)]
+where
+    I: Clone + Into<Offset>,
+{
+    fn try_flatten(&self, filler: &Filler) -> Result<Vec<u8>> {
+        flatten_offsets(self.iter().map(|(i, v)| (i, v)), filler, None)
+    }
+    fn try_flatten_padded(&self, filler: &Filler, length: usize) -> Result<Vec<u8>> {
+        let out = flatten_offsets(self.iter().map(|(i, v)| (i, v)), filler, Some(length))?;
+        pad(out, filler, length)
+    }
+}
+
+fn flatten_offsets<'a, 'b: 'a, I>(
+    entries: impl IntoIterator<Item = (&'a I, &'a Box<dyn Flatten + 'b>)>,
+    filler: &Filler,
+    length: Option<usize>,
+) -> Result<Vec<u8>>
+where
+    I: Clone + Into<Offset> + 'a,
+{
+    let bad_chars = context::get_bad_chars();
+    let mut items = Vec::new();
+    for (key, val) in entries {
+        let i = match key.clone().into() {
+            Offset::Start(i) => i,
+            Offset::Cyclic(subsequence) => {
+                cyclic_find(subsequence.as_slice()).ok_or(Error::NotCyclic(subsequence))?
             }
-            // If any of the values spliced out are `Some`, we're overlapping
-            if out
-                .splice(
-                    i..i + flattened.len(),
-                    flattened.into_iter().map(Some).collect::<Vec<Option<u8>>>(),
-                )
-                .any(|v: Option<u8>| v.is_some())
-            {
-                panic!("Values in flat() overlap");
+            Offset::End(back) => {
+                let end = length.ok_or(Error::NoLength)?;
+                end.checked_sub(back)
+                    .ok_or(Error::OffsetBeforeStart { back, end })?
             }
+        };
+        let flattened = flatten_item(&**val, filler, &bad_chars, || format!("{{{:#x}}}", i), i)?;
+        items.push((i, flattened));
+    }
+    items.sort_by_key(|(i, _)| *i);
+
+    // Prepare a vec with near the right capacity
+    let mut out: Vec<Option<u8>> = Vec::with_capacity(length.unwrap_or(0));
+    for (i, flattened) in items {
+        let end_i = i + flattened.len();
+        if end_i > out.len() {
+            out.extend(std::iter::repeat_n(None, end_i - out.len()));
+        }
+        // If any of the values spliced out are `Some`, we're overlapping
+        if let Some(overlap) = out
+            .splice(
+                i..i + flattened.len(),
+                flattened.into_iter().map(Some).collect::<Vec<Option<u8>>>(),
+            )
+            .position(|v: Option<u8>| v.is_some())
+        {
+            return Err(Error::Overlap {
+                offset: i + overlap,
+            });
         }
-        out.iter().map(|v| v.unwrap_or(b'a')).collect()
     }
+    let fill = filler.fill(out.len());
+    out.iter()
+        .zip(fill)
+        .enumerate()
+        .map(|(i, (v, fill))| match v {
+            Some(v) => Ok(*v),
+            None => check_filler(&[fill], i).map(|_| fill),
+        })
+        .collect()
 }
@@ -0,0 +1,268 @@
+use colored::Colorize;
+
+/// Dumps `data` in the same format as pwntools' `hexdump`, with the default [`Hexdump`]
+/// settings.
+/// ```
+/// use pwn::hexdump;
+/// colored::control::set_override(false);
+/// let dump = hexdump(b"AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAABB\n");
+/// assert_eq!(
+///     dump,
+///     "00000000  41 41 41 41  41 41 41 41  41 41 41 41  41 41 41 41  │AAAA│AAAA│AAAA│AAAA│
+/// *
+/// 00000020  42 42 0a                                            │BB·│
+/// 00000023"
+/// );
+/// ```
+pub fn hexdump(data: &[u8]) -> String {
+    Hexdump::new().dump(data)
+}
+
+/// Lazily dumps the bytes of `data` line by line, with the default [`Hexdump`] settings.
+pub fn hexdump_iter<I: IntoIterator<Item = u8>>(data: I) -> HexdumpIter<I::IntoIter> {
+    Hexdump::new().lines(data)
+}
+
+/// Configurable hexdump formatting.
+/// ```
+/// use pwn::Hexdump;
+/// colored::control::set_override(false);
+/// let dump = Hexdump::new().width(8).group(0).base(0x400000).dump(b"/bin/sh\0");
+/// assert_eq!(
+///     dump,
+///     "00400000  2f 62 69 6e 2f 73 68 00  │/bin/sh·│
+/// 00400008"
+/// );
+/// ```
+#[derive(Clone, Debug)]
+pub struct Hexdump {
+    width: usize,
+    base: usize,
+    group: usize,
+    skip: bool,
+    highlight: Vec<u8>,
+}
+
+impl Default for Hexdump {
+    fn default() -> Self {
+        Self {
+            width: 16,
+            base: 0,
+            group: 4,
+            skip: true,
+            highlight: Vec::new(),
+        }
+    }
+}
+
+impl Hexdump {
+    /// The default settings: 16 bytes per line, in groups of 4, starting at address 0 and
+    /// collapsing repeated lines.
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Show `width` bytes per line.
+    ///
+    /// # Panics
+    ///
+    /// Panics when `width` is 0.
+    pub fn width(mut self, width: usize) -> Self {
+        assert!(width > 0, "Hexdump width must be non-zero");
+        self.width = width;
+        self
+    }
+    /// Number the lines from `base` instead of 0.
+    pub fn base(mut self, base: usize) -> Self {
+        self.base = base;
+        self
+    }
+    /// Separate every `group` bytes with an extra space. If 0, bytes are not grouped.
+    pub fn group(mut self, group: usize) -> Self {
+        self.group = group;
+        self
+    }
+    /// Whether to collapse runs of identical lines into a single `*`.
+    pub fn skip(mut self, skip: bool) -> Self {
+        self.skip = skip;
+        self
+    }
+    /// Highlight each of `bytes`, such as bad characters, wherever they appear.
+    pub fn highlight(mut self, bytes: impl AsRef<[u8]>) -> Self {
+        self.highlight.extend_from_slice(bytes.as_ref());
+        self
+    }
+    /// Dump `data` to a string, with one line per row and no trailing newline.
+    pub fn dump(&self, data: &[u8]) -> String {
+        self.lines(data.iter().copied())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+    /// Lazily dump the bytes of `data` line by line.
+    pub fn lines<I: IntoIterator<Item = u8>>(&self, data: I) -> HexdumpIter<I::IntoIter> {
+        HexdumpIter {
+            config: self.clone(),
+            data: data.into_iter(),
+            offset: 0,
+            previous: None,
+            skipping: false,
+            done: false,
+        }
+    }
+
+    fn group_size(&self) -> usize {
+        match self.group {
+            0 => self.width,
+            n => n,
+        }
+    }
+
+    fn format_line(&self, offset: usize, line: &[u8]) -> String {
+        let group = self.group_size();
+        let mut hex = String::new();
+        let mut text = String::from("│");
+        for i in 0..self.width {
+            if i != 0 {
+                hex.push_str(if i % group == 0 { "  " } else { " " });
+            }
+            let byte = match line.get(i) {
+                Some(byte) => *byte,
+                None => {
+                    hex.push_str("  ");
+                    continue;
+                }
+            };
+            let printable = match byte {
+                0x20..=0x7e => (byte as char).to_string(),
+                _ => "·".to_string(),
+            };
+            let (byte_hex, printable) = self.colour(byte, format!("{:02x}", byte), printable);
+            hex.push_str(&byte_hex);
+            text.push_str(&printable);
+            if (i + 1) % group == 0 && i + 1 < line.len() {
+                text.push('│');
+            }
+        }
+        text.push('│');
+        format!("{:08x}  {}  {}", self.base + offset, hex, text)
+    }
+
+    fn colour(&self, byte: u8, hex: String, printable: String) -> (String, String) {
+        if self.highlight.contains(&byte) {
+            return (hex.on_red().to_string(), printable.on_red().to_string());
+        }
+        match byte {
+            0x00 | 0x0a => (hex.red().to_string(), printable.red().to_string()),
+            0xff => (hex.green().to_string(), printable.green().to_string()),
+            0x20..=0x7e => (hex, printable),
+            _ => (hex.dimmed().to_string(), printable.dimmed().to_string()),
+        }
+    }
+}
+
+/// An iterator over the lines of a hexdump, created by [`hexdump_iter`] or [`Hexdump::lines`].
+pub struct HexdumpIter<I> {
+    config: Hexdump,
+    data: I,
+    offset: usize,
+    previous: Option<Vec<u8>>,
+    skipping: bool,
+    done: bool,
+}
+
+impl<I: Iterator<Item = u8>> Iterator for HexdumpIter<I> {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        loop {
+            if self.done {
+                return None;
+            }
+            let line: Vec<u8> = self.data.by_ref().take(self.config.width).collect();
+            if line.is_empty() {
+                self.done = true;
+                return Some(format!("{:08x}", self.config.base + self.offset));
+            }
+            let offset = self.offset;
+            self.offset += line.len();
+            if self.config.skip && self.previous.as_ref() == Some(&line) {
+                if self.skipping {
+                    continue;
+                }
+                self.skipping = true;
+                return Some("*".to_string());
+            }
+            self.skipping = false;
+            let formatted = self.config.format_line(offset, &line);
+            self.previous = Some(line);
+            return Some(formatted);
+        }
+    }
+}
+
+/// Parse a hexdump in the format produced by [`hexdump`] back into bytes, expanding `*` lines.
+/// Colours and any width, grouping or base address are accepted.
+///
+/// Returns `None` if the dump is malformed.
+/// ```
+/// use pwn::{hexdump, unhexdump};
+/// let data = b"AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAABB\n";
+/// assert_eq!(unhexdump(&hexdump(data)).unwrap(), data);
+/// ```
+pub fn unhexdump(dump: &str) -> Option<Vec<u8>> {
+    let mut data = Vec::new();
+    let mut base = None;
+    let mut previous: Vec<u8> = Vec::new();
+    let mut repeat = false;
+    for line in strip_ansi(dump).lines() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        if line == "*" {
+            repeat = true;
+            continue;
+        }
+        let (offset, rest) = line.split_once(' ').unwrap_or((line, ""));
+        let offset = usize::from_str_radix(offset, 16).ok()?;
+        let offset = offset.checked_sub(*base.get_or_insert(offset))?;
+        if repeat {
+            // Repeat the previous line up to this offset
+            let gap = offset.checked_sub(data.len())?;
+            if previous.is_empty() || gap % previous.len() != 0 {
+                return None;
+            }
+            while data.len() < offset {
+                data.extend_from_slice(&previous);
+            }
+            repeat = false;
+        }
+        if offset != data.len() {
+            return None;
+        }
+        let hex = rest.split(['│', '|']).next().unwrap_or("");
+        previous = hex
+            .split_whitespace()
+            .map(|byte| u8::from_str_radix(byte, 16).ok())
+            .collect::<Option<_>>()?;
+        data.extend_from_slice(&previous);
+    }
+    Some(data)
+}
+
+fn strip_ansi(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            // Skip a CSI sequence such as `\x1b[31m`
+            for c in chars.by_ref() {
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
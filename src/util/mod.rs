@@ -1,8 +1,17 @@
 //! A collection of miscellaneous utilities.
 
-/// Utilities for packing unsigned integers to `Vec<u8>`s of appropriate size.
+/// Utilities for packing integers to `Vec<u8>`s of appropriate size.
 pub mod packing;
 pub use packing::*;
 /// Utility for flattening a collection of data to bytes.
 pub mod flat;
 pub use flat::*;
+/// Pwntools-compatible hexdumps.
+pub mod hexdump;
+pub use hexdump::*;
+/// De Bruijn patterns for finding offsets in overflows.
+pub mod cyclic;
+pub use cyclic::*;
+/// Encoding and bit manipulation helpers.
+pub mod fiddling;
+pub use fiddling::*;
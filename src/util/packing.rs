@@ -1,9 +1,34 @@
+use std::iter;
+
 use crate::context::{
     self,
-    Endianness::{Big, Little},
+    Endianness::{self, Big, Little},
 };
-use crate::Bits;
+use crate::error::Result;
+use crate::{Bits, Error};
 use byteorder::{BigEndian, ByteOrder, LittleEndian};
+use num_traits::ToPrimitive;
+
+/// Whether an integer is packed as signed (two's complement) or unsigned, which determines the
+/// range of values that fit in a given width.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+#[allow(missing_docs)]
+pub enum Sign {
+    #[default]
+    Unsigned,
+    Signed,
+}
+
+impl From<bool> for Sign {
+    /// `true` is [`Sign::Signed`], as in [`context::set_signed`].
+    fn from(signed: bool) -> Self {
+        if signed {
+            Sign::Signed
+        } else {
+            Sign::Unsigned
+        }
+    }
+}
 
 /// Packs a `u8` to a 1-byte `Vec`.
 pub fn p8(v: u8) -> Vec<u8> {
@@ -12,7 +37,7 @@ pub fn p8(v: u8) -> Vec<u8> {
 
 /// Packs a `u16` to a 2-byte `Vec`.
 pub fn p16(v: u16) -> Vec<u8> {
-    let mut res: Vec<u8> = std::iter::repeat(0).take(2).collect();
+    let mut res: Vec<u8> = vec![0; 2];
     match context::get_endianess() {
         Big => BigEndian::write_u16(&mut res, v),
         Little => LittleEndian::write_u16(&mut res, v),
@@ -22,7 +47,7 @@ pub fn p16(v: u16) -> Vec<u8> {
 
 /// Packs a `u32` to a 4-byte `Vec`.
 pub fn p32(v: u32) -> Vec<u8> {
-    let mut res: Vec<u8> = std::iter::repeat(0).take(4).collect();
+    let mut res: Vec<u8> = vec![0; 4];
     match context::get_endianess() {
         Big => BigEndian::write_u32(&mut res, v),
         Little => LittleEndian::write_u32(&mut res, v),
@@ -32,7 +57,7 @@ pub fn p32(v: u32) -> Vec<u8> {
 
 /// Packs a `u64` to an 8-byte `Vec`.
 pub fn p64(v: u64) -> Vec<u8> {
-    let mut res: Vec<u8> = std::iter::repeat(0).take(8).collect();
+    let mut res: Vec<u8> = vec![0; 8];
     match context::get_endianess() {
         Big => BigEndian::write_u64(&mut res, v),
         Little => LittleEndian::write_u64(&mut res, v),
@@ -40,71 +65,362 @@ pub fn p64(v: u64) -> Vec<u8> {
     res
 }
 
-/// Unpacks a 1 byte `Vec` to a `u8`.
-///
-/// # Panics
-///
-/// Panics when `v.len() < 1`.
+/// Packs an `i8` to a 1-byte `Vec`.
+pub fn p8_signed(v: i8) -> Vec<u8> {
+    p8(v as u8)
+}
+
+/// Packs an `i16` to a 2-byte `Vec`.
+pub fn p16_signed(v: i16) -> Vec<u8> {
+    p16(v as u16)
+}
+
+/// Packs an `i32` to a 4-byte `Vec`.
+/// ```
+/// use pwn::{context, p32_signed, Endianness};
+/// context::set_endianess(Endianness::Little);
+/// assert_eq!(p32_signed(-1), [0xff, 0xff, 0xff, 0xff]);
+/// ```
+pub fn p32_signed(v: i32) -> Vec<u8> {
+    p32(v as u32)
+}
+
+/// Packs an `i64` to an 8-byte `Vec`.
+pub fn p64_signed(v: i64) -> Vec<u8> {
+    p64(v as u64)
+}
+
+/// Packs an integer to a 1-byte `Vec`, ignoring the [`context`].
+/// Returns `None` if the integer is out of range for `sign`.
+pub fn p8_with<T: ToPrimitive>(v: T, endian: Endianness, sign: Sign) -> Option<Vec<u8>> {
+    pack_width(v, 1, endian, sign)
+}
+
+/// Packs an integer to a 2-byte `Vec`, ignoring the [`context`].
+/// Returns `None` if the integer is out of range for `sign`.
+pub fn p16_with<T: ToPrimitive>(v: T, endian: Endianness, sign: Sign) -> Option<Vec<u8>> {
+    pack_width(v, 2, endian, sign)
+}
+
+/// Packs an integer to a 4-byte `Vec`, ignoring the [`context`].
+/// Returns `None` if the integer is out of range for `sign`.
+/// ```
+/// use pwn::{p32_with, Endianness::Big, Sign};
+/// assert_eq!(p32_with(0x100, Big, Sign::Unsigned).unwrap(), [0, 0, 1, 0]);
+/// assert_eq!(p32_with(-2, Big, Sign::Signed).unwrap(), [0xff, 0xff, 0xff, 0xfe]);
+/// assert_eq!(p32_with(-2, Big, Sign::Unsigned), None);
+/// ```
+pub fn p32_with<T: ToPrimitive>(v: T, endian: Endianness, sign: Sign) -> Option<Vec<u8>> {
+    pack_width(v, 4, endian, sign)
+}
+
+/// Packs an integer to an 8-byte `Vec`, ignoring the [`context`].
+/// Returns `None` if the integer is out of range for `sign`.
+pub fn p64_with<T: ToPrimitive>(v: T, endian: Endianness, sign: Sign) -> Option<Vec<u8>> {
+    pack_width(v, 8, endian, sign)
+}
+
+// Pad `v` with zeroes to `N` bytes on its most significant side, truncating it if it's longer
+fn padded<const N: usize>(v: &[u8], endian: Endianness) -> [u8; N] {
+    let mut buf = [0; N];
+    let len = v.len().min(N);
+    match endian {
+        Big => buf[N - len..].copy_from_slice(&v[..len]),
+        Little => buf[..len].copy_from_slice(&v[..len]),
+    }
+    buf
+}
+
+/// Unpacks a 1 byte `Vec` to a `u8`. An empty `Vec` is unpacked as 0.
 pub fn u8(v: &[u8]) -> u8 {
-    v[0]
+    v.first().copied().unwrap_or(0)
 }
 
-/// Unpacks a 2 byte `Vec` to a `u16`.
-///
-/// # Panics
-///
-/// Panics when `v.len() < 2`.
+/// Unpacks a 2 byte `Vec` to a `u16`. Shorter `Vec`s are padded with zeroes.
 pub fn u16(v: &[u8]) -> u16 {
     match context::get_endianess() {
-        Big => BigEndian::read_u16(v),
-        Little => LittleEndian::read_u16(v),
+        Big => BigEndian::read_u16(&padded::<2>(v, Big)),
+        Little => LittleEndian::read_u16(&padded::<2>(v, Little)),
     }
 }
 
-/// Unpacks a 4 byte `Vec` to a `u32`.
-///
-/// # Panics
-///
-/// Panics when `v.len() < 4`.
+/// Unpacks a 4 byte `Vec` to a `u32`. Shorter `Vec`s are padded with zeroes.
 pub fn u32(v: &[u8]) -> u32 {
     match context::get_endianess() {
-        Big => BigEndian::read_u32(v),
-        Little => LittleEndian::read_u32(v),
+        Big => BigEndian::read_u32(&padded::<4>(v, Big)),
+        Little => LittleEndian::read_u32(&padded::<4>(v, Little)),
     }
 }
 
-/// Unpacks an 8 byte `Vec` to a `u64`.
-///
-/// # Panics
-///
-/// Panics when `v.len() < 8`.
+/// Unpacks an 8 byte `Vec` to a `u64`. Shorter `Vec`s are padded with zeroes.
+/// ```
+/// use pwn::{context, u64, Endianness};
+/// context::set_endianess(Endianness::Little);
+/// assert_eq!(u64(b"\x10\x32\x54\x76\xfe\x7f"), 0x7ffe76543210);
+/// ```
 pub fn u64(v: &[u8]) -> u64 {
     match context::get_endianess() {
-        Big => BigEndian::read_u64(v),
-        Little => LittleEndian::read_u64(v),
+        Big => BigEndian::read_u64(&padded::<8>(v, Big)),
+        Little => LittleEndian::read_u64(&padded::<8>(v, Little)),
+    }
+}
+
+// Pack `v` into `bytes` bytes, checking that it fits in the range given by `sign`
+fn pack_width<T: ToPrimitive>(
+    v: T,
+    bytes: usize,
+    endian: Endianness,
+    sign: Sign,
+) -> Option<Vec<u8>> {
+    let bits = bytes.checked_mul(8)?;
+    if bits == 0 {
+        return None;
     }
+    let (le, fill) = match sign {
+        Sign::Unsigned => {
+            let v = v.to_u128()?;
+            if bits < 128 && v >> bits != 0 {
+                return None;
+            }
+            (v.to_le_bytes(), 0)
+        }
+        Sign::Signed => {
+            let v = v.to_i128()?;
+            if bits < 128 && (v < -(1 << (bits - 1)) || v >= 1 << (bits - 1)) {
+                return None;
+            }
+            (v.to_le_bytes(), if v < 0 { 0xff } else { 0 })
+        }
+    };
+    let mut res: Vec<u8> = le
+        .into_iter()
+        .chain(iter::repeat(fill))
+        .take(bytes)
+        .collect();
+    if endian == Big {
+        res.reverse();
+    }
+    Some(res)
 }
 
 /// Automatically packs an integer in a [`context`] sensitive way.
 /// Returns `None` if the passed integer is too large to be casted down.
+///
+/// If the context is [signed](context::set_signed), the integer must fit in the signed range
+/// of the word size instead.
 pub fn pack<T>(v: T) -> Option<Vec<u8>>
 where
-    T: num_traits::ToPrimitive,
+    T: ToPrimitive,
 {
-    match context::get_bits() {
-        Bits::Eight => Some(p8(v.to_u8()?)),
-        Bits::Sixteen => Some(p16(v.to_u16()?)),
-        Bits::ThirtyTwo => Some(p32(v.to_u32()?)),
-        Bits::SixtyFour => Some(p64(v.to_u64()?)),
+    pack_bits(v, context::get_bits() as usize)
+}
+
+/// Packs an integer as signed, using the [`context`] word size and endianness regardless of
+/// its signedness.
+/// Returns `None` if the passed integer is out of the signed range of the word size.
+pub fn pack_signed<T>(v: T) -> Option<Vec<u8>>
+where
+    T: ToPrimitive,
+{
+    pack_width(
+        v,
+        context::get_bits() as usize / 8,
+        context::get_endianess(),
+        Sign::Signed,
+    )
+}
+
+/// Packs an integer into `bits` bits, using the [`context`] endianness and signedness.
+/// Any multiple of 8 is supported, and integers are sign-extended beyond 128 bits.
+/// Returns `None` if `bits` isn't a non-zero multiple of 8, or the integer is out of range.
+/// ```
+/// use pwn::{context, pack_bits, Endianness};
+/// context::set_endianess(Endianness::Little);
+/// assert_eq!(pack_bits(0x123456, 24).unwrap(), [0x56, 0x34, 0x12]);
+/// assert_eq!(pack_bits(0x1000000, 24), None);
+/// assert_eq!(pack_bits(0x1234, 12), None);
+/// ```
+pub fn pack_bits<T>(v: T, bits: usize) -> Option<Vec<u8>>
+where
+    T: ToPrimitive,
+{
+    if !bits.is_multiple_of(8) {
+        return None;
+    }
+    pack_width(
+        v,
+        bits / 8,
+        context::get_endianess(),
+        context::get_signed().into(),
+    )
+}
+
+/// Packs an integer as [`pack`] does, returning an error if it doesn't fit in the word size or
+/// contains one of the [`context`] bad characters.
+/// ```
+/// use pwn::{context, pack_checked, AMD64};
+/// context::set_arch(AMD64);
+/// context::set_bad_chars(b"\n");
+/// assert!(pack_checked(0x40120a).is_err());
+/// assert!(pack_checked(0x401234).is_ok());
+/// ```
+pub fn pack_checked<T>(v: T) -> Result<Vec<u8>>
+where
+    T: ToPrimitive,
+{
+    pack_checked_with(v, &context::get_bad_chars())
+}
+
+/// Packs an integer as [`pack`] does, returning an error if it doesn't fit in the word size or
+/// contains one of `bad_chars`.
+/// ```
+/// use pwn::{context, pack_checked_with, AMD64};
+/// context::set_arch(AMD64);
+/// assert!(pack_checked_with(0x40120a, b"\n").is_err());
+/// assert!(pack_checked_with(0x40120a, b"").is_ok());
+/// ```
+pub fn pack_checked_with<T>(v: T, bad_chars: &[u8]) -> Result<Vec<u8>>
+where
+    T: ToPrimitive,
+{
+    let packed = pack(v).ok_or_else(|| Error::ValueTooLarge(context::get_bits()))?;
+    check_bad_chars_with(&packed, bad_chars)?;
+    Ok(packed)
+}
+
+/// Check that `data` doesn't contain any of the [`context`] bad characters, returning an error
+/// with the first one found.
+pub fn check_bad_chars(data: &[u8]) -> Result<()> {
+    check_bad_chars_with(data, &context::get_bad_chars())
+}
+
+/// Check that `data` doesn't contain any of `bad_chars`, returning an error with the first one
+/// found.
+/// ```
+/// use pwn::{check_bad_chars_with, Error};
+/// let err = check_bad_chars_with(b"/bin/sh\0", b"\0\n").unwrap_err();
+/// assert!(matches!(err, Error::BadChar { byte: 0, offset: 7, .. }));
+/// ```
+pub fn check_bad_chars_with(data: &[u8], bad_chars: &[u8]) -> Result<()> {
+    match data.iter().position(|b| bad_chars.contains(b)) {
+        Some(offset) => Err(Error::BadChar {
+            byte: data[offset],
+            offset,
+            item: String::new(),
+        }),
+        None => Ok(()),
     }
 }
 
-/// Automatically unpacks a buffer to an integer in a [`context`] sensitive way.
+/// Automatically unpacks a buffer to an integer in a [`context`] sensitive way. Buffers shorter
+/// than the word size are padded with zeroes.
+///
+/// If the context is [signed](context::set_signed), the result is sign-extended, so that it
+/// can be cast to an `i64`.
 pub fn unpack(v: &[u8]) -> u64 {
-    match context::get_bits() {
+    unpack_bits(v, context::get_bits())
+}
+
+fn unpack_bits(v: &[u8], bits: Bits) -> u64 {
+    let signed = context::get_signed();
+    match bits {
+        Bits::Eight if signed => u8(v) as i8 as u64,
+        Bits::Sixteen if signed => u16(v) as i16 as u64,
+        Bits::ThirtyTwo if signed => u32(v) as i32 as u64,
         Bits::Eight => u8(v) as u64,
         Bits::Sixteen => u16(v) as u64,
         Bits::ThirtyTwo => u32(v) as u64,
         Bits::SixtyFour => u64(v),
     }
 }
+
+fn check_len(v: &[u8], max: usize) -> Result<()> {
+    match v.len() {
+        len if len > max => Err(Error::TooLong { len, max }),
+        _ => Ok(()),
+    }
+}
+
+/// Unpacks up to 2 bytes to a `u16`, padding with zeroes.
+/// Returns an error if `v` is too long.
+pub fn u16_padded(v: &[u8]) -> Result<u16> {
+    check_len(v, 2)?;
+    Ok(u16(v))
+}
+
+/// Unpacks up to 4 bytes to a `u32`, padding with zeroes.
+/// Returns an error if `v` is too long.
+pub fn u32_padded(v: &[u8]) -> Result<u32> {
+    check_len(v, 4)?;
+    Ok(u32(v))
+}
+
+/// Unpacks up to 8 bytes to a `u64`, padding with zeroes.
+/// Returns an error if `v` is too long.
+/// ```
+/// use pwn::{context, u64_padded, Endianness};
+/// context::set_endianess(Endianness::Little);
+/// assert_eq!(u64_padded(b"\x10\x32\x54\x76\xfe\x7f").unwrap(), 0x7ffe76543210);
+/// assert!(u64_padded(b"AAAAAAAAA").is_err());
+/// ```
+pub fn u64_padded(v: &[u8]) -> Result<u64> {
+    check_len(v, 8)?;
+    Ok(u64(v))
+}
+
+/// Unpacks up to a word of bytes in a [`context`] sensitive way, as [`unpack`] does.
+/// Returns an error if `v` is longer than the word size.
+pub fn unpack_padded(v: &[u8]) -> Result<u64> {
+    check_len(v, context::get_bits() as usize / 8)?;
+    Ok(unpack(v))
+}
+
+/// Unpacks a buffer made up of `word_size` words, using the [`context`] endianness and
+/// signedness.
+/// Returns an error if the buffer isn't a whole number of words.
+/// ```
+/// use pwn::{context, unpack_many, Bits, Endianness};
+/// context::set_endianess(Endianness::Little);
+/// assert_eq!(unpack_many(b"\x01\x00\x02\x00", Bits::Sixteen).unwrap(), [1, 2]);
+/// assert!(unpack_many(b"\x01\x00\x02", Bits::Sixteen).is_err());
+/// ```
+pub fn unpack_many(buf: &[u8], word_size: Bits) -> Result<Vec<u64>> {
+    let size = word_size as usize / 8;
+    if !buf.len().is_multiple_of(size) {
+        return Err(Error::Misaligned {
+            len: buf.len(),
+            word_size: size,
+        });
+    }
+    Ok(buf
+        .chunks(size)
+        .map(|word| unpack_bits(word, word_size))
+        .collect())
+}
+
+/// Unpacks an address leaked by a program, such as the result of
+/// [`recvline`](crate::tubes::Tube::recvline).
+///
+/// A trailing [`context`] newline is stripped, and the rest is padded with zeroes to the word
+/// size, so that a pointer truncated at its first null byte is recovered. Returns an error if
+/// the leak is empty or longer than the word size.
+/// ```
+/// use pwn::{context, leak_address, AMD64};
+/// context::set_arch(AMD64);
+/// assert_eq!(leak_address(b"\x10\x32\x54\x76\xfe\x7f\n").unwrap(), 0x7ffe76543210);
+/// ```
+pub fn leak_address(bytes: &[u8]) -> Result<u64> {
+    let newline = context::get_newline();
+    let bytes = bytes.strip_suffix(newline.as_slice()).unwrap_or(bytes);
+    if bytes.is_empty() {
+        return Err(Error::EmptyLeak);
+    }
+    let bits = context::get_bits();
+    check_len(bytes, bits as usize / 8)?;
+    Ok(match bits {
+        Bits::Eight => u8(bytes) as u64,
+        Bits::Sixteen => u16(bytes) as u64,
+        Bits::ThirtyTwo => u32(bytes) as u64,
+        Bits::SixtyFour => u64(bytes),
+    })
+}
@@ -0,0 +1,50 @@
+use pwn::context;
+use pwn::{Args, Level};
+use std::time::Duration;
+
+#[test]
+fn test_parse() {
+    let args = Args::parse_from(
+        [
+            "REMOTE",
+            "HOST=10.10.10.10",
+            "PORT=1337",
+            "NOASLR=0",
+            "./binary",
+            "lower=1",
+        ],
+        [
+            ("PWNLIB_PORT", "80"),
+            ("PWNLIB_NOTERM", "1"),
+            ("PATH", "/bin"),
+        ],
+    );
+    assert!(args.flag("REMOTE"));
+    assert_eq!(args.value("REMOTE"), Some(""));
+    assert_eq!(args.value("HOST"), Some("10.10.10.10"));
+    // Arguments override the environment
+    assert_eq!(args.get::<u16>("PORT"), Some(1337));
+    assert!(args.flag("NOTERM"));
+    assert!(!args.flag("NOASLR"));
+    assert!(!args.flag("DEBUG"));
+    assert_eq!(args.get::<u16>("HOST"), None);
+    assert_eq!(args.value("PATH"), None);
+    assert_eq!(args.rest(), ["./binary", "lower=1"]);
+}
+
+#[test]
+fn test_apply() {
+    let args = Args::parse_from(["LOG_LEVEL=warn", "TIMEOUT=1.5"], [] as [(&str, &str); 0]);
+    args.apply();
+    assert_eq!(context::get_log_level(), Level::WARN);
+    assert_eq!(context::get_timeout(), Some(Duration::from_millis(1500)));
+    // Invalid timeouts are ignored rather than blocking forever
+    for timeout in ["-1", "nan", "inf", "soon"] {
+        Args::parse_from([format!("TIMEOUT={}", timeout)], [] as [(&str, &str); 0]).apply();
+        assert_eq!(context::get_timeout(), Some(Duration::from_millis(1500)));
+    }
+
+    Args::parse_from(["DEBUG"], [] as [(&str, &str); 0]).apply();
+    assert_eq!(context::get_log_level(), Level::DEBUG);
+    assert_eq!(context::get_default(), context::snapshot());
+}
@@ -0,0 +1,19 @@
+// Applying the magic arguments changes the default context, so this runs in
+// its own process rather than alongside `test_apply`.
+use pwn::context;
+use std::time::Duration;
+
+#[test]
+fn test_init() {
+    std::env::set_var("PWNLIB_TIMEOUT", "7");
+    // Reading the arguments doesn't configure the context, even from another thread
+    let timeout = std::thread::spawn(|| {
+        assert_eq!(pwn::args().value("TIMEOUT"), Some("7"));
+        drop(pwn::progress("Reading"));
+        context::get_timeout()
+    });
+    assert_eq!(timeout.join().unwrap(), None);
+    assert_eq!(context::get_timeout(), None);
+    pwn::args::init();
+    assert_eq!(context::get_timeout(), Some(Duration::from_secs(7)));
+}
@@ -1,6 +1,6 @@
 use pwn::context::Bits::SixtyFour;
 use pwn::context::Endianness::Little;
-use pwn::context::{self, AMD64, I386};
+use pwn::context::{self, Arch, Machine, Os, AMD64, ARM, I386, MIPS64EL, THUMB};
 
 /// Test `Context`
 #[test]
@@ -11,4 +11,91 @@ fn test_context() {
     assert_eq!(context::get_arch(), AMD64);
     assert_eq!(context::get_endianess(), Little);
     assert_eq!(context::get_bits(), SixtyFour);
+
+    assert_eq!(context::get_os(), Os::Linux);
+    context::set_os("FreeBSD".parse().unwrap());
+    assert_eq!(context::get_os().to_string(), "freebsd");
+    assert!(matches!(
+        "plan9".parse::<Os>(),
+        Err(pwn::Error::UnknownOs(_))
+    ));
+    assert!(context::get_terminal().is_empty());
+    context::set_terminal(["tmux", "splitw", "-h"]);
+    assert_eq!(context::get_terminal(), ["tmux", "splitw", "-h"]);
+    assert!(context::get_bad_chars().is_empty());
+    context::local(|ctx| {
+        ctx.bad_chars(*b"\n\0");
+        assert_eq!(context::get_bad_chars(), b"\n\0");
+    });
+    assert!(context::get_bad_chars().is_empty());
+}
+
+/// Test the architecture database
+#[test]
+fn test_arch() {
+    assert_ne!(ARM, I386);
+    assert_eq!(ARM.machine, Machine::Arm);
+    assert_eq!(ARM.stack_pointer(), "sp");
+    assert_eq!(THUMB.program_counter(), "pc");
+    assert_eq!(I386.calling_convention().arguments, [] as [&str; 0]);
+    assert_eq!(I386.syscall().instruction, "int 0x80");
+    assert_eq!(AMD64.syscall().arguments[3], "r10");
+    assert_eq!(AMD64.alignment(), 8);
+    assert!(AMD64.registers().contains(&"r15"));
+
+    for arch in [AMD64, I386, ARM, THUMB, MIPS64EL] {
+        assert_eq!(arch.to_string().parse::<Arch>().unwrap(), arch);
+    }
+    assert_eq!("x86_64".parse::<Arch>().unwrap(), AMD64);
+    assert_eq!("ARM64".parse::<Arch>().unwrap().name(), "aarch64");
+    let armeb: Arch = "arm-big".parse().unwrap();
+    assert_eq!(armeb.to_string(), "arm-32-big");
+    assert!(matches!(
+        "z80".parse::<Arch>(),
+        Err(pwn::Error::UnknownArch(name)) if name == "z80"
+    ));
+}
+
+/// Test scoped overrides
+#[test]
+fn test_local() {
+    use pwn::context::Endianness::Big;
+    use pwn::context::{Bits::ThirtyTwo, MIPS};
+    use pwn::Level;
+    use std::time::Duration;
+
+    context::set_arch(AMD64);
+    let packed = context::local(|ctx| {
+        ctx.arch(MIPS).log_level(Level::DEBUG);
+        assert_eq!(context::get_log_level(), Level::DEBUG);
+        context::local(|inner| {
+            inner.bits(SixtyFour).timeout(Some(Duration::from_secs(1)));
+            assert_eq!(context::get_arch().machine, Machine::Mips);
+            assert_eq!(context::get_timeout(), Some(Duration::from_secs(1)));
+        });
+        assert_eq!(context::get_bits(), ThirtyTwo);
+        assert_eq!(context::get_timeout(), None);
+        pwn::p32(0x11223344)
+    });
+    assert_eq!(packed, b"\x11\x22\x33\x44");
+    assert_eq!(context::get_arch(), AMD64);
+    assert_eq!(context::get_log_level(), Level::INFO);
+
+    {
+        let ctx = context::push();
+        ctx.endian(Big);
+        // Free setters are also undone by the guard
+        context::set_bits(ThirtyTwo);
+        assert_eq!(context::get_endianess(), Big);
+    }
+    assert_eq!(context::get_arch(), AMD64);
+
+    // Restored even when unwinding
+    let _ = std::panic::catch_unwind(|| {
+        context::local(|ctx| {
+            ctx.arch(I386);
+            panic!()
+        })
+    });
+    assert_eq!(context::get_arch(), AMD64);
 }
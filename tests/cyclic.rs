@@ -0,0 +1,80 @@
+use std::collections::HashSet;
+
+use pwn::context::{self, Endianness::Big, AMD64, I386};
+use pwn::{
+    cyclic, cyclic_find, cyclic_find_with, cyclic_metasploit, cyclic_metasploit_find, cyclic_with,
+};
+
+#[test]
+fn test_cyclic() {
+    context::set_arch(I386);
+    assert_eq!(cyclic(16), b"aaaabaaacaaadaaa");
+    context::set_arch(AMD64);
+    assert_eq!(cyclic(16), b"aaaaaaaabaaaaaaa");
+
+    // Every window of the full pattern is unique
+    let pattern = cyclic_with(3usize.pow(4) + 3, b"abc", 4);
+    let windows: HashSet<_> = pattern.windows(4).collect();
+    assert_eq!(windows.len(), 3usize.pow(4));
+    assert_eq!(cyclic_with(10, b"01", 3), b"0001011100");
+}
+
+#[test]
+#[should_panic]
+fn test_cyclic_too_long() {
+    cyclic_with(3usize.pow(4) + 4, b"abc", 4);
+}
+
+#[test]
+fn test_cyclic_find() {
+    context::set_arch(I386);
+    assert_eq!(cyclic_find(b"caaa"), Some(8));
+    assert_eq!(cyclic_find(&cyclic(1000)[400..404]), Some(400));
+    assert_eq!(cyclic_find(0x61616164u32), Some(12));
+    context::set_endianess(Big);
+    assert_eq!(cyclic_find(0x64616161u32), Some(12));
+
+    context::set_arch(AMD64);
+    assert_eq!(cyclic_find(b"caaaaaaa"), Some(16));
+    assert_eq!(cyclic_find(0x6161616161616164u64), Some(24));
+    // The end of a pattern far too long to generate
+    assert_eq!(cyclic_find(b"zaaaaaaa"), Some(26usize.pow(8) - 1));
+    assert_eq!(cyclic_find(b"yzzzzzzz"), Some(26usize.pow(8) - 9));
+    // Shorter than the word size, looked up in the 4-byte pattern
+    assert_eq!(cyclic_find(b"caaa"), Some(8));
+    assert_eq!(cyclic_find(0x61616167), Some(24));
+    assert_eq!(cyclic_find(0x61616167u32), Some(24));
+    // Outside the alphabet
+    assert_eq!(cyclic_find(b"AAAAAAAA"), None);
+    assert_eq!(cyclic_find(b""), None);
+
+    assert_eq!(cyclic_find_with(b"ABCC", b"ABC", 4), Some(43));
+    assert_eq!(cyclic_find_with(b"ABC", b"ABC", 4), None);
+}
+
+#[test]
+fn test_cyclic_find_every_window() {
+    for (alphabet, n) in [
+        (&b"a"[..], 3),
+        (b"01", 1),
+        (b"01", 5),
+        (b"01", 8),
+        (b"abc", 4),
+        (b"abcd", 3),
+        (b"abcdef", 2),
+    ] {
+        let pattern = cyclic_with(alphabet.len().pow(n as u32) + n - 1, alphabet, n);
+        for (offset, window) in pattern.windows(n).enumerate() {
+            assert_eq!(cyclic_find_with(window, alphabet, n), Some(offset));
+        }
+    }
+}
+
+#[test]
+fn test_cyclic_metasploit() {
+    assert_eq!(cyclic_metasploit(30), b"Aa0Aa1Aa2Aa3Aa4Aa5Aa6Aa7Aa8Aa9");
+    assert_eq!(cyclic_metasploit(20280).len(), 20280);
+    assert_eq!(cyclic_metasploit_find(b"Ab0A"), Some(30));
+    assert_eq!(cyclic_metasploit_find(b"Zz9"), Some(20277));
+    assert_eq!(cyclic_metasploit_find(b"zzzz"), None);
+}
@@ -0,0 +1,38 @@
+use pwn::context::{self, Context, AMD64, I386, MIPS};
+use std::thread;
+
+/// Test the process-wide default `Context`. Kept in its own binary, as changing the default
+/// affects every test thread started afterwards.
+#[test]
+fn test_default_context() {
+    assert_eq!(context::get_default(), Context::default());
+    context::set_arch(AMD64);
+    // Other threads are unaffected by the thread-local override
+    thread::spawn(|| assert_eq!(context::get_arch(), I386))
+        .join()
+        .unwrap();
+
+    context::set_default(context::snapshot());
+    thread::spawn(|| {
+        assert_eq!(context::get_arch(), AMD64);
+        context::set_arch(MIPS);
+    })
+    .join()
+    .unwrap();
+    assert_eq!(context::get_arch(), AMD64);
+
+    // Threads which have already used their context keep it
+    context::set_default(Context::default());
+    assert_eq!(context::get_arch(), AMD64);
+
+    let ctx = context::snapshot();
+    let spawned = thread::spawn(move || {
+        ctx.apply();
+        context::get_arch()
+    });
+    assert_eq!(spawned.join().unwrap(), AMD64);
+
+    context::set_arch(MIPS);
+    let bound = thread::spawn(context::bind(context::get_arch));
+    assert_eq!(bound.join().unwrap(), MIPS);
+}
@@ -1,4 +1,7 @@
-use pwn::Elf;
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+
+use pwn::{context, info, init_logger, Checksec, Elf, Error, Pattern, Relro, AMD64};
 
 #[test]
 fn test_elf() {
@@ -14,7 +17,7 @@ fn test_elf() {
     ];
     assert_eq!(got.len(), elf.got().len());
     got.iter().for_each(|(sym, addr)| {
-        assert_eq!(elf.got().get(sym).unwrap(), addr);
+        assert_eq!(elf.got().get(*sym).unwrap(), addr);
     });
     assert_eq!(1, elf.plt().len());
     assert_eq!(*elf.plt().get("puts").unwrap(), 4144);
@@ -52,7 +55,7 @@ fn test_elf() {
     ];
     assert_eq!(syms.len(), elf.symbols().len());
     syms.iter().for_each(|(sym, addr)| {
-        assert_eq!(elf.symbols().get(sym).unwrap(), addr);
+        assert_eq!(elf.symbols().get(*sym).unwrap(), addr);
     });
 
     elf.set_address(0x5000);
@@ -60,7 +63,418 @@ fn test_elf() {
     assert_eq!(*elf.symbols().get("puts").unwrap(), 0x5000 + 4144);
 }
 
+#[test]
+fn test_plt_variants() {
+    let plt = |bytes| {
+        let mut plt: Vec<(String, usize)> = Elf::from_bytes(bytes)
+            .plt()
+            .iter()
+            .map(|(name, addr)| (name.clone(), *addr))
+            .collect();
+        plt.sort();
+        plt
+    };
+    let expect = |entries: &[(&str, usize)]| {
+        let mut entries: Vec<(String, usize)> = entries
+            .iter()
+            .map(|(name, addr)| (name.to_string(), *addr))
+            .collect();
+        entries.sort();
+        entries
+    };
+    // `.plt.sec` and `.plt.got` with IBT
+    assert_eq!(
+        plt(res::HARDENED),
+        expect(&[
+            ("__cxa_finalize", 0x1060),
+            ("__stack_chk_fail", 0x1070),
+            ("__strcpy_chk", 0x1080),
+            ("__printf_chk", 0x1090),
+        ])
+    );
+    // `.plt` and an 8-byte `.plt.got` with `-z now`
+    assert_eq!(
+        plt(res::BINDNOW),
+        expect(&[
+            ("strcpy", 0x1030),
+            ("printf", 0x1040),
+            ("__cxa_finalize", 0x1050)
+        ])
+    );
+    assert_eq!(
+        plt(res::I386),
+        expect(&[("foo", 0x8049010), ("bar", 0x8049020)])
+    );
+    // PIC stubs relative to `ebx`, in `.plt.sec`
+    assert_eq!(
+        plt(res::I386_PIE),
+        expect(&[("foo", 0x1030), ("bar", 0x1040)])
+    );
+    assert_eq!(
+        plt(res::PLT_ARM),
+        expect(&[("foo", 0x10144), ("bar", 0x10150), ("baz", 0x10160)])
+    );
+    assert_eq!(
+        plt(res::PLT_AARCH64),
+        expect(&[("foo", 0x4001f0), ("bar", 0x400200), ("baz", 0x400218)])
+    );
+    assert_eq!(
+        plt(res::PLT_MIPS),
+        expect(&[("foo", 0x4f8150), ("bar", 0x4f8160), ("baz", 0x4f8170)])
+    );
+    // `.MIPS.stubs`, which name their dynamic symbol rather than a GOT slot
+    assert_eq!(
+        plt(res::PLT_MIPSEL),
+        expect(&[("foo", 0x400110), ("bar", 0x400120), ("baz", 0x400130)])
+    );
+}
+
+#[test]
+fn test_sections_and_segments() {
+    let mut elf = Elf::from_bytes(res::ELF);
+    assert_eq!(elf.sections().count(), 30);
+    let bss = elf.get_section_by_name(".bss").unwrap();
+    assert_eq!(bss.address(), 0x4030);
+    assert_eq!(bss.size(), 8);
+    assert!(bss.data().is_empty());
+    assert!(bss.is_writable() && !bss.is_executable());
+    let interp = elf.get_section_by_name(".interp").unwrap();
+    assert_eq!(interp.data(), b"/lib64/ld-linux-x86-64.so.2\0");
+    assert!(elf.get_section_by_name(".nonexistent").is_none());
+
+    assert_eq!(elf.segments().count(), 13);
+    let exec: Vec<_> = elf.executable_segments().collect();
+    assert_eq!(exec.len(), 1);
+    assert_eq!(exec[0].address(), 0x1000);
+    assert_eq!(exec[0].size(), 0x169);
+    let writable: Vec<_> = elf.writable_segments().collect();
+    assert_eq!(writable.len(), 1);
+    assert_eq!(writable[0].address(), 0x3de8);
+    assert_eq!(writable[0].file_size(), 0x248);
+
+    elf.set_address(0x5000);
+    assert_eq!(elf.get_section_by_name(".bss").unwrap().address(), 0x9030);
+    assert_eq!(elf.executable_segments().next().unwrap().address(), 0x6000);
+    // Sections which aren't loaded aren't rebased
+    assert_eq!(elf.get_section_by_name(".symtab").unwrap().address(), 0);
+}
+
+#[test]
+fn test_read() {
+    context::set_arch(AMD64);
+    let mut elf = Elf::from_bytes(res::ELF);
+    assert_eq!(elf.vaddr_to_offset(0x3de8), Some(0x2de8));
+    assert_eq!(elf.vaddr_to_offset(0x4030), None);
+    assert_eq!(elf.offset_to_vaddr(0x2de8), Some(0x3de8));
+    assert_eq!(elf.read(0x318, 6).unwrap(), b"/lib64");
+    assert_eq!(elf.string(0x318).unwrap(), b"/lib64/ld-linux-x86-64.so.2");
+    // `__dso_handle` points to itself
+    assert_eq!(elf.unpack(0x4028).unwrap(), 0x4028);
+    // `.bss` is not backed by the file
+    assert_eq!(
+        elf.read(0x402c, 8).unwrap(),
+        b"\x00\x00\x00\x00\x00\x00\x00\x00"
+    );
+    assert!(matches!(
+        elf.read(0x4038, 1),
+        Err(Error::UnmappedAddress(0x4038))
+    ));
+
+    elf.set_address(0x5000);
+    assert_eq!(elf.vaddr_to_offset(0x8de8), Some(0x2de8));
+    assert_eq!(elf.offset_to_vaddr(0x2de8), Some(0x8de8));
+    assert_eq!(elf.string(0x5318).unwrap(), b"/lib64/ld-linux-x86-64.so.2");
+    assert!(elf.read(0x318, 1).is_err());
+}
+
+#[test]
+fn test_checksec() {
+    let default = Elf::from_bytes(res::ELF).checksec();
+    assert_eq!(
+        default,
+        Checksec {
+            arch: Some(AMD64),
+            relro: Relro::Partial,
+            canary: false,
+            nx: true,
+            pie: true,
+            address: 0,
+            rpath: None,
+            runpath: None,
+            fortified: vec![],
+            shstk: false,
+            ibt: false,
+            stripped: false,
+        }
+    );
+
+    let hardened = Elf::from_bytes(res::HARDENED).checksec();
+    assert_eq!(hardened.relro, Relro::Full);
+    assert!(hardened.canary && hardened.nx && hardened.pie);
+    assert_eq!(hardened.fortified, ["printf", "strcpy"]);
+    assert!(hardened.shstk && hardened.ibt && hardened.stripped);
+
+    let unhardened = Elf::from_bytes(res::UNHARDENED).checksec();
+    assert_eq!(unhardened.relro, Relro::None);
+    assert!(!unhardened.canary && !unhardened.nx && !unhardened.pie);
+    assert_eq!(unhardened.address, 0x400000);
+
+    let rpath = Elf::from_bytes(res::RPATH).checksec();
+    assert_eq!(rpath.runpath.as_deref(), Some("/tmp/rpath/placeholder/dir"));
+
+    assert_eq!(
+        unhardened.to_string(),
+        "    Arch:     amd64-64-little
+    RELRO:    No RELRO
+    Stack:    No canary found
+    NX:       NX disabled
+    PIE:      No PIE (0x400000)
+    Stripped: No"
+    );
+}
+
+#[derive(Clone, Default)]
+struct Capture(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Capture {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_checksec_log() {
+    colored::control::set_override(true);
+    let capture = Capture::default();
+    pwn::__redirect_terminal(capture.clone());
+    init_logger();
+
+    let checksec = Elf::from_bytes(res::UNHARDENED).checksec();
+    checksec.log("'\x1b[2Jbinary'");
+    info!("\x1b[2Jplain");
+    let output = String::from_utf8(capture.0.lock().unwrap().clone()).unwrap();
+    // Values are coloured, while escape sequences from the messages themselves are not written
+    assert!(output.contains("    RELRO:    \x1b[31mNo RELRO\x1b[0m\n"));
+    assert!(output.contains("    Arch:     amd64-64-little\n"));
+    assert!(output.contains("'\\x1b[2Jbinary'"));
+    assert!(output.contains("\\x1b[2Jplain"));
+    assert!(!output.contains("\x1b[2J"));
+}
+
+#[test]
+fn test_search() {
+    let mut elf = Elf::from_bytes(res::ELF);
+    assert_eq!(elf.search("ld-linux").collect::<Vec<_>>(), [0x31f]);
+    // `pop rdx; ret` in `main`
+    assert_eq!(
+        elf.search(b"\x5a\xc3").executable().collect::<Vec<_>>(),
+        [0x104f]
+    );
+    assert_eq!(elf.search(b"\x5a\xc3").writable().count(), 0);
+    // `lea rdi, [rip + ??]; call`
+    let lea = Pattern::from_hex("48 8d 3d ?? ?? ?? ?? e8").unwrap();
+    assert_eq!(elf.search(lea.clone()).collect::<Vec<_>>(), [0x1041]);
+    assert!(Pattern::from_hex("48 8d 3").is_none());
+    let masked = Pattern::masked(*b"\x20\x40", *b"\xf0\xff");
+    assert!(elf.search(masked).writable().any(|addr| addr == 0x4028));
+    // `__dso_handle`
+    assert_eq!(
+        elf.search(b"\x28\x40\x00\x00")
+            .writable()
+            .aligned(8)
+            .collect::<Vec<_>>(),
+        [0x4028]
+    );
+    assert_eq!(elf.search(b"").count(), 0);
+
+    elf.set_address(0x5000);
+    assert_eq!(elf.search(lea).collect::<Vec<_>>(), [0x6041]);
+}
+
+#[test]
+fn test_patch() {
+    context::set_arch(AMD64);
+    let mut elf = Elf::from_bytes(res::ELF);
+    elf.write(0x4020, b"patched").unwrap();
+    assert_eq!(elf.read(0x4020, 7).unwrap(), b"patched");
+    elf.pack(0x4028, 0xdeadbeefu64).unwrap();
+    assert_eq!(elf.unpack(0x4028).unwrap(), 0xdeadbeef);
+    // The original bytes are untouched
+    assert_eq!(Elf::from_bytes(res::ELF).unpack(0x4028).unwrap(), 0x4028);
+    assert!(matches!(
+        elf.write(0x4030, b"bss"),
+        Err(Error::UnmappedAddress(0x4030))
+    ));
+
+    elf.patch_interpreter("/tmp/ld.so").unwrap();
+    assert_eq!(elf.string(0x318).unwrap(), b"/tmp/ld.so");
+    assert!(matches!(
+        elf.patch_interpreter(&"A".repeat(28)),
+        Err(Error::NoSpace {
+            needed: 29,
+            available: 28
+        })
+    ));
+    assert!(matches!(elf.set_rpath("/tmp"), Err(Error::Missing(_))));
+
+    let stack = |elf: &Elf| {
+        elf.segments()
+            .find(|seg| seg.p_type() == 0x6474e551) // PT_GNU_STACK
+            .unwrap()
+            .is_executable()
+    };
+    assert!(!stack(&elf));
+    elf.disable_nx().unwrap();
+    assert!(stack(&elf));
+
+    let path = std::env::temp_dir().join("pwn-test-patch");
+    elf.save(&path).unwrap();
+    let saved = Elf::open(&path).unwrap();
+    assert_eq!(saved.string(0x318).unwrap(), b"/tmp/ld.so");
+    assert!(stack(&saved));
+    drop(saved);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_patch_symbols() {
+    context::set_arch(AMD64);
+    let mut elf = Elf::from_bytes(res::ELF);
+    assert_eq!(*elf.symbols().get("__cxa_finalize").unwrap(), 16376);
+    assert_eq!(*elf.got().get("puts").unwrap(), 16408);
+
+    // Give the `__cxa_finalize` import a value in `.dynsym`
+    let dynstr = elf.get_section_by_name(".dynstr").unwrap();
+    let name = dynstr
+        .data()
+        .windows(16)
+        .position(|w| w == b"\0__cxa_finalize\0")
+        .unwrap() as u32
+        + 1;
+    let dynsym = elf.get_section_by_name(".dynsym").unwrap();
+    let sym = dynsym
+        .data()
+        .chunks(24)
+        .position(|sym| sym[..4] == name.to_le_bytes())
+        .unwrap();
+    let value = dynsym.address() + sym * 24 + 8;
+    elf.pack(value, 0x1234u64).unwrap();
+    assert_eq!(*elf.symbols().get("__cxa_finalize").unwrap(), 0x1234);
+
+    // Move the GOT slot of `puts` in `.rela.plt`
+    let rela = elf.get_section_by_name(".rela.plt").unwrap().address();
+    elf.pack(rela, 0x4020u64).unwrap();
+    assert_eq!(*elf.got().get("puts").unwrap(), 0x4020);
+
+    elf.set_address(0x5000);
+    elf.pack(value + 0x5000, 0x2345u64).unwrap();
+    assert_eq!(
+        *elf.symbols().get("__cxa_finalize").unwrap(),
+        0x5000 + 0x2345
+    );
+    assert_eq!(*elf.got().get("puts").unwrap(), 0x5000 + 0x4020);
+}
+
+#[test]
+fn test_set_rpath() {
+    let mut elf = Elf::from_bytes(res::RPATH);
+    let dynstr = |elf: &Elf| elf.get_section_by_name(".dynstr").unwrap().data().to_vec();
+    let find = |haystack: &[u8], needle: &[u8]| haystack.windows(needle.len()).any(|w| w == needle);
+    assert!(find(&dynstr(&elf), b"/tmp/rpath/placeholder/dir\0"));
+    elf.set_rpath("/tmp/libs").unwrap();
+    assert!(find(&dynstr(&elf), b"/tmp/libs\0"));
+    assert!(!find(&dynstr(&elf), b"placeholder"));
+    assert!(matches!(
+        elf.set_rpath(&"A".repeat(64)),
+        Err(Error::NoSpace { .. })
+    ));
+}
+
+#[test]
+fn test_invalid_elf() {
+    assert!(matches!(Elf::parse(b"\x7fELF..."), Err(Error::Parse(_))));
+    assert!(matches!(
+        Elf::open("/nonexistent/binary"),
+        Err(Error::Io(_))
+    ));
+}
+
+#[test]
+fn test_owned_elf() {
+    let path = std::env::temp_dir().join("pwn-test-owned-elf");
+    std::fs::write(&path, res::ELF).unwrap();
+    let opened = Elf::open(&path).unwrap();
+    assert_eq!(opened.path(), &path);
+    assert_eq!(*opened.plt().get("puts").unwrap(), 4144);
+    drop(opened);
+    std::fs::remove_file(&path).unwrap();
+
+    let owned = Elf::parse_vec(res::ELF.to_vec()).unwrap();
+    assert_eq!(*owned.got().get("puts").unwrap(), 16408);
+}
+
+#[test]
+fn test_missing_plt_section() {
+    // Rename `.plt` (and friends) in the section header string table
+    let mut bytes = res::ELF.to_vec();
+    for i in 0..bytes.len() - 4 {
+        if &bytes[i..i + 4] == b".plt" {
+            bytes[i + 1] = b'x';
+        }
+    }
+    let elf = Elf::parse(&bytes).unwrap();
+    assert!(elf.plt().is_empty());
+    assert_eq!(*elf.got().get("puts").unwrap(), 16408);
+}
+
+#[test]
+fn test_arch() {
+    use pwn::context::Endianness::{Big, Little};
+    use pwn::{AARCH64, ARM, I386, MIPS, MIPSEL};
+
+    let cases: [(&[u8], _, _); 6] = [
+        (res::ELF, AMD64, Little),
+        (res::I386, I386, Little),
+        (res::PLT_ARM, ARM, Little),
+        (res::PLT_AARCH64, AARCH64, Little),
+        (res::PLT_MIPS, MIPS, Big),
+        (res::PLT_MIPSEL, MIPSEL, Little),
+    ];
+    for (bytes, arch, endian) in cases {
+        let elf = Elf::parse(bytes).unwrap();
+        assert_eq!(elf.arch(), Some(arch));
+        assert_eq!(elf.endian(), endian);
+    }
+
+    Elf::parse(res::PLT_MIPS).unwrap().set_context();
+    assert_eq!(context::get_arch(), MIPS);
+    assert_eq!(pwn::p32(0x11223344), b"\x11\x22\x33\x44");
+    Elf::parse(res::ELF).unwrap().set_context();
+    assert_eq!(context::get_arch(), AMD64);
+}
+
 mod res {
+    // `gcc -Os -Wl,-rpath,/tmp/rpath/placeholder/dir`, stripped
+    pub static RPATH: &[u8] = include_bytes!("res/rpath");
+    // `gcc -O2 -D_FORTIFY_SOURCE=2 -fstack-protector-all -fcf-protection=full
+    //  -Wl,-z,now,-z,ibt,-z,shstk`, stripped
+    pub static HARDENED: &[u8] = include_bytes!("res/hardened");
+    // `gcc -O0 -fno-stack-protector -fcf-protection=none -no-pie -z execstack -Wl,-z,norelro`
+    pub static UNHARDENED: &[u8] = include_bytes!("res/unhardened");
+    // `gcc -O2 -fcf-protection=none -Wl,-z,now`, stripped
+    pub static BINDNOW: &[u8] = include_bytes!("res/bindnow");
+    // `ld -m elf_i386` against a shared library, without and with `-pie -z ibtplt`
+    pub static I386: &[u8] = include_bytes!("res/i386");
+    pub static I386_PIE: &[u8] = include_bytes!("res/i386-pie");
+    // Generated by `res/mkplt.py`
+    pub static PLT_ARM: &[u8] = include_bytes!("res/plt-arm");
+    pub static PLT_AARCH64: &[u8] = include_bytes!("res/plt-aarch64");
+    pub static PLT_MIPS: &[u8] = include_bytes!("res/plt-mips");
+    pub static PLT_MIPSEL: &[u8] = include_bytes!("res/plt-mipsel");
     pub static ELF: &[u8] = b"\x7f\x45\x4c\x46\x02\x01\x01\x00\x00\x00\x00\x00\x00\x00\x00\x00\x03\x00\x3e\x00\x01\x00\x00\x00\x60\x10\x00\x00\x00\x00\
 \x00\x00\x40\x00\x00\x00\x00\x00\x00\x00\xc0\x36\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x40\x00\x38\x00\x0d\x00\x40\x00\
 \x1e\x00\x1d\x00\x06\x00\x00\x00\x04\x00\x00\x00\x40\x00\x00\x00\x00\x00\x00\x00\x40\x00\x00\x00\x00\x00\x00\x00\x40\x00\
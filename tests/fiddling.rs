@@ -0,0 +1,106 @@
+use pwn::context::{self, Endianness::Big, AMD64, I386};
+use pwn::util::fiddling::*;
+use pwn::{check_bad_chars, xor, Error};
+
+#[test]
+fn test_encodings() {
+    let data: Vec<u8> = (0..=255).collect();
+    assert_eq!(unhex(enhex(&data)).unwrap(), data);
+    assert_eq!(unhex("").unwrap(), b"");
+    assert!(matches!(
+        unhex("00g0"),
+        Err(Error::InvalidEncoding {
+            encoding: "hex",
+            offset: 2
+        })
+    ));
+
+    for len in 0..8 {
+        assert_eq!(b64d(b64e(&data[..len])).unwrap(), &data[..len]);
+    }
+    assert_eq!(b64e(&data[..3]), "AAEC");
+    assert_eq!(b64e(b"\xff\xfe"), "//4=");
+    assert_eq!(b64d(" aGVsbG8gd29ybGQ=\n").unwrap(), b"hello world");
+    assert!(b64d("aGVsb").is_err());
+
+    assert_eq!(urldecode(urlencode(&data)).unwrap(), data);
+    assert_eq!(urldecode("a+b%20c").unwrap(), b"a+b c");
+    assert!(urldecode("%2").is_err());
+    assert!(urldecode("%zz").is_err());
+}
+
+#[test]
+fn test_xor() {
+    assert_eq!(xor([b"\x01\x02\x03", b"\x01\x02\x03"]), b"\0\0\0");
+    assert_eq!(xor([&b"abcdef"[..], b"\x20"]), b"ABCDEF");
+    assert_eq!(xor(Vec::<Vec<u8>>::new()), b"");
+
+    context::set_arch(I386);
+    assert_eq!(xor!(b"\0\0\0\0", 0x41424344u32), b"DCBA");
+    context::set_endianess(Big);
+    assert_eq!(xor!(b"\0\0\0\0", 0x41424344u32, b"\x01"), b"@CBE");
+
+    // Operands are encoded to avoid bad characters, so may contain them
+    context::set_bad_chars(*b"\0");
+    assert_eq!(xor!(b"/bin/sh\0", 0x01010101u32), b".cho.ri\x01");
+    context::set_bad_chars(*b"");
+}
+
+#[test]
+#[should_panic]
+fn test_xor_empty() {
+    xor([&b"AAAA"[..], b""]);
+}
+
+#[test]
+fn test_xor_key() {
+    let shellcode = b"\x31\xc0\x50\x68\x2f\x2f\x73\x68\x0a\x00";
+    context::set_arch(I386);
+    context::set_bad_chars(*b"\0\n");
+    let (key, xored) = xor_key(shellcode).unwrap();
+    assert_eq!(key.len(), 4);
+    assert!(check_bad_chars(&key).is_ok());
+    assert!(check_bad_chars(&xored).is_ok());
+    assert_eq!(xor([&xored, &key]), shellcode);
+
+    context::set_arch(AMD64);
+    assert_eq!(xor_key(shellcode).unwrap().0.len(), 8);
+    // Every key byte is either bad, or turns some byte into a bad one
+    let every_byte: Vec<u8> = (0..=255).collect();
+    assert_eq!(xor_key_with(&every_byte, b"\0", 1), None);
+    assert_eq!(xor_key_with(b"", b"\0", 2).unwrap(), (vec![1, 1], vec![]));
+}
+
+#[test]
+fn test_bits() {
+    assert_eq!(bits_str(b"\x0f\xa0"), "0000111110100000");
+    assert_eq!(bits(b"").len(), 0);
+    assert_eq!(unbits(bits(b"\x12\x34")), b"\x12\x34");
+    assert_eq!(
+        unbits([false, false, false, false, false, false, false, true, true]),
+        b"\x01\x80"
+    );
+    assert_eq!(bitswap(b"\x12\x34"), b"\x48\x2c");
+}
+
+#[test]
+fn test_rotation() {
+    context::set_arch(AMD64);
+    assert_eq!(rol(0x8000000000000001, 1), 3);
+    assert_eq!(ror(3, 1), 0x8000000000000001);
+    assert_eq!(rol(1, 64), 1);
+    assert_eq!(bitswap_int(1), 0x8000000000000000);
+    context::set_arch(I386);
+    assert_eq!(ror(1, 1), 0x80000000);
+    assert_eq!(rol(0x12345678, 36), 0x23456781);
+    assert_eq!(bitswap_int(0x80000000), 1);
+
+    assert_eq!(rol_with(0b101, 1, 3), 0b011);
+    assert_eq!(ror_with(0b101, 1, 3), 0b110);
+    assert_eq!(rol_with(0xff01, 4, 8), 0x10);
+    assert_eq!(rol_with(1, 127, 128), 1 << 127);
+    assert_eq!(ror_with(1 << 127, 127, 128), 1);
+    for k in 0..20 {
+        assert_eq!(ror_with(rol_with(0x1234, k, 13), k, 13), 0x1234 & 0x1fff);
+    }
+}
@@ -1,4 +1,12 @@
-use pwn::{context, flat, Flatten, I386};
+// The first tests pass references, as flat() took them before it accepted values
+#![allow(clippy::needless_borrows_for_generic_args)]
+
+use std::collections::BTreeMap;
+
+use pwn::{
+    context, cyclic, cyclic_find, flat, offsets, p32, Bits, Endianness, Error, Filler, FlatBuilder,
+    Flatten, Offset, AMD64, I386,
+};
 
 #[test]
 fn test_flatten() {
@@ -17,11 +25,231 @@ fn test_flatten() {
 fn test_overlap() {
     context::set_arch(I386);
     // Suppress backtrace during tests
-    use std::panic::PanicInfo;
-    let f = |_: &PanicInfo| {};
+    use std::panic::PanicHookInfo;
+    let f = |_: &PanicHookInfo| {};
     std::panic::set_hook(Box::new(f));
     flat(&*vec![
         (0usize, Box::new(0x41414141u32) as Box<dyn Flatten>),
         (2, Box::new(b"Overlap") as _),
     ]);
 }
+
+#[test]
+fn test_flatten_types() {
+    context::set_arch(I386);
+    assert_eq!(flat(-1i8), [0xff; 4]);
+    assert_eq!(flat([-2i16, 1]), b"\xfe\xff\xff\xff\x01\x00\x00\x00");
+    assert_eq!(flat(0x41424344usize), b"DCBA");
+    assert_eq!(flat(String::from("AB")), b"AB");
+    assert_eq!(flat(vec![b"AB".to_vec(), p32(1)]), b"AB\x01\x00\x00\x00");
+    assert_eq!(flat((Some(1u32), None::<u32>, "!")), b"\x01\x00\x00\x00!");
+    let boxed: Vec<Box<dyn Flatten>> = vec![Box::new(1u32), Box::new("A")];
+    assert_eq!(flat(&boxed), b"\x01\x00\x00\x00A");
+    // Nested structures are flattened in order
+    assert_eq!(flat((("A", ["B", "C"]), vec![("D", 0x45u8)])), b"ABCDE");
+    // Pairs of offsets and boxed values are placed like a map
+    let pairs: Vec<(i32, Box<dyn Flatten>)> = vec![(-1, Box::new(b"B")), (0, Box::new("A"))];
+    assert_eq!(FlatBuilder::new().length(4).flat(pairs).unwrap(), b"AaaB");
+    let dict: BTreeMap<usize, Box<dyn Flatten>> = BTreeMap::from([
+        (24, Box::new(b"Hello!\xff") as _),
+        (12, Box::new(0x41414141u32) as _),
+    ]);
+    assert_eq!(flat(&dict), b"aaaaaaaaaaaaAAAAaaaaaaaaHello!\xff");
+
+    context::set_arch(AMD64);
+    assert_eq!(flat(-1i64), [0xff; 8]);
+    assert_eq!(
+        flat!(b"AAAA", 0x1234u64, -1i32, p32(2), String::from("B")),
+        b"AAAA\x34\x12\x00\x00\x00\x00\x00\x00\xff\xff\xff\xff\xff\xff\xff\xff\x02\x00\x00\x00B"
+    );
+    assert_eq!(flat!(), b"");
+}
+
+#[test]
+#[should_panic]
+fn test_flatten_out_of_range() {
+    context::set_arch(I386);
+    flat(i64::MIN);
+}
+
+fn offsets(items: Vec<(usize, Box<dyn Flatten>)>) -> BTreeMap<usize, Box<dyn Flatten>> {
+    items.into_iter().collect()
+}
+
+#[test]
+fn test_flat_builder() {
+    context::set_arch(I386);
+    let payload = FlatBuilder::new()
+        .filler(Filler::cyclic())
+        .flat(offsets(vec![(12, Box::new(0x42424242u32))]))
+        .unwrap();
+    assert_eq!(payload, b"aaaabaaacaaaBBBB");
+    // The offset of filler which ends up in a register can be found
+    let payload = FlatBuilder::new()
+        .filler(Filler::cyclic())
+        .length(64)
+        .flat(b"AAAA")
+        .unwrap();
+    assert_eq!(payload.len(), 64);
+    assert_eq!(cyclic_find(&payload[40..44]), Some(40));
+
+    assert_eq!(
+        FlatBuilder::new()
+            .filler(b'\x90')
+            .length(8)
+            .flat(b"\xcc")
+            .unwrap(),
+        b"\xcc\x90\x90\x90\x90\x90\x90\x90"
+    );
+    assert_eq!(
+        FlatBuilder::new()
+            .filler(Filler::iter(*b"XY"))
+            .flat(offsets(vec![(5, Box::new(b"!"))]))
+            .unwrap(),
+        b"XYXYX!"
+    );
+
+    let builder = FlatBuilder::new()
+        .bits(Bits::SixtyFour)
+        .endian(Endianness::Big);
+    assert_eq!(
+        builder.flat(1u32).unwrap(),
+        b"\x00\x00\x00\x00\x00\x00\x00\x01"
+    );
+    // The override only applies to the call
+    assert_eq!(context::get_bits(), Bits::ThirtyTwo);
+    assert_eq!(flat(1u32), b"\x01\x00\x00\x00");
+    context::set_arch(AMD64);
+    assert_eq!(
+        FlatBuilder::new().bits(Bits::Sixteen).flat(-1i64).unwrap(),
+        b"\xff\xff"
+    );
+}
+
+#[test]
+fn test_flat_builder_errors() {
+    context::set_arch(I386);
+    let overlapping = offsets(vec![
+        (0, Box::new(0x41414141u32)),
+        (2, Box::new(b"Overlap")),
+    ]);
+    assert!(matches!(
+        FlatBuilder::new().flat(overlapping),
+        Err(Error::Overlap { offset: 2 })
+    ));
+    assert!(matches!(
+        FlatBuilder::new().length(2).flat(b"AAAA"),
+        Err(Error::LengthExceeded { len: 4, length: 2 })
+    ));
+    assert!(matches!(
+        FlatBuilder::new().flat((1u32, 0x100000000u64)),
+        Err(Error::ValueTooLarge(Bits::ThirtyTwo))
+    ));
+}
+
+#[test]
+fn test_offsets() {
+    context::set_arch(I386);
+    assert_eq!(Offset::from(-4), Offset::End(4));
+    assert_eq!(Offset::from(4u8), Offset::Start(4));
+    assert_eq!(Offset::from(b"haaa"), Offset::Cyclic(b"haaa".to_vec()));
+
+    // Byte string keys are found in the cyclic pattern
+    let payload = FlatBuilder::new()
+        .filler(Filler::cyclic())
+        .flat(offsets! { b"haaa" => 0x42424242u32, 4 => b"XXXX" })
+        .unwrap();
+    assert_eq!(&payload[4..8], b"XXXX");
+    assert_eq!(&payload[8..28], &cyclic(28)[8..]);
+    assert_eq!(&payload[28..], b"BBBB");
+    assert_eq!(flat! { "caaa" => b"C", 0u64 => b"A" }, b"AaaaaaaaC");
+
+    // Negative keys count back from the length
+    assert_eq!(
+        FlatBuilder::new()
+            .length(12)
+            .flat(offsets! { -4 => 0x44434241u32, 0 => b"!" })
+            .unwrap(),
+        b"!aaaaaaaABCD"
+    );
+    // Keys shorter than the word size are found in the pattern of their own length
+    context::set_arch(AMD64);
+    assert_eq!(
+        flat! { b"haaa" => 0x42424242u32 },
+        b"aaaaaaaaaaaaaaaaaaaaaaaaaaaaBBBB\0\0\0\0"
+    );
+    assert_eq!(flat! { b"baaaaaaa" => b"!" }, b"aaaaaaaa!");
+    // The word size override applies to cyclic keys too
+    assert_eq!(
+        FlatBuilder::new()
+            .bits(Bits::ThirtyTwo)
+            .flat(offsets! { b"baaa" => b"!" })
+            .unwrap(),
+        b"aaaa!"
+    );
+    // Offsets nest, relative to the start of their own map
+    assert_eq!(flat((b"AAAA", offsets! { 2 => b"B" })), b"AAAAaaB");
+}
+
+#[test]
+fn test_offset_errors() {
+    context::set_arch(I386);
+    assert!(matches!(
+        FlatBuilder::new().flat(offsets! { b"AAAA" => b"!" }),
+        Err(Error::NotCyclic(s)) if s == b"AAAA"
+    ));
+    assert!(matches!(
+        FlatBuilder::new().flat(offsets! { -4 => b"!" }),
+        Err(Error::NoLength)
+    ));
+    assert!(matches!(
+        FlatBuilder::new().length(2).flat(offsets! { -4 => b"!" }),
+        Err(Error::OffsetBeforeStart { back: 4, end: 2 })
+    ));
+    assert!(matches!(
+        FlatBuilder::new()
+            .length(8)
+            .flat(offsets! { 0 => b"AAAAAA", -4 => b"BBBB" }),
+        Err(Error::Overlap { offset: 4 })
+    ));
+}
+
+#[test]
+fn test_bad_chars() {
+    context::set_arch(I386);
+    let bad_char = |result| match result {
+        Err(Error::BadChar { byte, offset, item }) => (byte, offset, item),
+        other => panic!("Expected a bad character, got {:?}", other),
+    };
+    let builder = FlatBuilder::new().bad_chars(*b"\n\0");
+    assert_eq!(
+        bad_char(builder.flat((b"AAAA", [0x41414141u32, 0x4141410au32]))),
+        (b'\n', 8, ".1[1]".to_string())
+    );
+    assert_eq!(
+        bad_char(builder.flat(offsets! { 4 => vec!["AAAA", "BB\0B"] })),
+        (0, 10, "{0x4}[1]".to_string())
+    );
+    assert_eq!(bad_char(builder.flat(0x0au32)), (b'\n', 0, String::new()));
+    // Filler and padding are checked too
+    let builder = builder.filler(b'\0');
+    assert_eq!(
+        bad_char(builder.flat(offsets! { 4 => b"A" })),
+        (0, 0, "filler".to_string())
+    );
+    assert_eq!(
+        bad_char(builder.length(8).flat(b"AAAA")),
+        (0, 4, "filler".to_string())
+    );
+
+    // The context bad characters are used by default
+    context::set_bad_chars(*b" ");
+    assert_eq!(
+        bad_char(FlatBuilder::new().flat(("A", "B C"))),
+        (b' ', 2, ".1".to_string())
+    );
+    assert!(FlatBuilder::new().bad_chars(*b"").flat("B C").is_ok());
+    assert!(std::panic::catch_unwind(|| flat("B C")).is_err());
+    context::set_bad_chars(*b"");
+    assert_eq!(flat("B C"), b"B C");
+}
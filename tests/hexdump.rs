@@ -0,0 +1,60 @@
+use pwn::{hexdump, hexdump_iter, unhexdump, Hexdump};
+
+#[test]
+fn test_hexdump() {
+    colored::control::set_override(false);
+    assert_eq!(hexdump(b""), "00000000");
+    let data = [0u8; 64];
+    assert_eq!(
+        hexdump(&data),
+        "00000000  00 00 00 00  00 00 00 00  00 00 00 00  00 00 00 00  │····│····│····│····│
+*
+00000040"
+    );
+    assert_eq!(
+        Hexdump::new().width(4).skip(false).dump(&data[..8]),
+        "00000000  00 00 00 00  │····│
+00000004  00 00 00 00  │····│
+00000008"
+    );
+    assert_eq!(
+        Hexdump::new()
+            .width(6)
+            .group(2)
+            .base(0x1000)
+            .dump(b"ABCDEFG"),
+        "00001000  41 42  43 44  45 46  │AB│CD│EF│
+00001006  47                   │G│
+00001007"
+    );
+
+    // Lines are produced lazily, so infinite streams can be dumped
+    let mut lines = hexdump_iter((0..=255u8).cycle());
+    assert!(lines.next().unwrap().starts_with("00000000  00 01 02 03"));
+    assert!(lines.nth(15).unwrap().starts_with("00000100  00 01 02 03"));
+
+    colored::control::set_override(true);
+    let highlighted = Hexdump::new().highlight(b"B").dump(b"AB");
+    assert!(highlighted.contains("\x1b[41m42\x1b[0m"));
+    assert!(!highlighted.contains("\x1b[41m41"));
+    colored::control::unset_override();
+}
+
+#[test]
+fn test_unhexdump() {
+    let data: Vec<u8> = (0..100).map(|i| if i < 80 { 0x41 } else { i }).collect();
+    for dump in [
+        hexdump(&data),
+        Hexdump::new().width(8).group(0).base(0x400000).dump(&data),
+        Hexdump::new().highlight(b"A").dump(&data),
+    ] {
+        assert_eq!(unhexdump(&dump).unwrap(), data);
+    }
+    // ASCII separators, as used by `xxd`-like tools and older pwntools
+    assert_eq!(
+        unhexdump("00000000  41 42 0a  |AB.|\n00000003").unwrap(),
+        b"AB\n"
+    );
+    assert_eq!(unhexdump("00000000  4g"), None);
+    assert_eq!(unhexdump("00000010  41\n00000000  41"), None);
+}
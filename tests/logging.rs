@@ -0,0 +1,80 @@
+use pwn::{context, critical, debug, failure, info, init_logger, progress, success, Level};
+
+#[test]
+fn test_file_logging() {
+    let path = std::env::temp_dir().join("pwn-test-logging.log");
+    let _ = std::fs::remove_file(&path);
+    pwn::init_logger_with_file(&path).unwrap();
+    // Initialising again is harmless
+    init_logger();
+
+    info!("plain");
+    info!("\x1b[1;31mcoloured\x1b[0m value");
+    success!("it worked: {}", 1);
+    failure!("it failed");
+    critical!("on fire");
+    debug!("hidden");
+    context::set_log_level(Level::DEBUG);
+    debug!("shown");
+    context::set_log_level(Level::WARN);
+    success!("hidden");
+    context::set_log_level(Level::INFO);
+
+    let p = progress("Leaking");
+    p.status("0x41");
+    p.success("0x4142");
+    progress("Waiting").failure("timed out");
+    drop(progress("Dropped"));
+    let workers: Vec<_> = (0..4)
+        .map(|i| std::thread::spawn(move || progress(format!("Worker {}", i)).success("ok")))
+        .collect();
+    for worker in workers {
+        worker.join().unwrap();
+    }
+
+    let log = std::fs::read_to_string(&path).unwrap();
+    let lines: Vec<_> = log
+        .lines()
+        .map(|line| line.split_once(' ').unwrap())
+        .collect();
+    assert!(lines.iter().all(|(time, _)| time.ends_with('Z')));
+    let mut messages: Vec<_> = lines.iter().map(|(_, message)| *message).collect();
+    let mut workers = messages.split_off(12);
+    workers.sort_unstable();
+    let mut expected: Vec<_> = (0..4)
+        .flat_map(|i| {
+            [
+                format!("[*] pwn::progress: Worker {}", i),
+                format!("[+] pwn::progress: Worker {}: ok", i),
+            ]
+        })
+        .collect();
+    expected.sort_unstable();
+    assert_eq!(workers, expected);
+    assert_eq!(
+        messages,
+        [
+            "[*] logging: plain",
+            "[*] logging: coloured value",
+            "[+] logging: it worked: 1",
+            "[-] logging: it failed",
+            "[CRITICAL] logging: on fire",
+            "[|] logging: shown",
+            "[*] pwn::progress: Leaking",
+            "[+] pwn::progress: Leaking: 0x4142",
+            "[*] pwn::progress: Waiting",
+            "[-] pwn::progress: Waiting: timed out",
+            "[*] pwn::progress: Dropped",
+            "[+] pwn::progress: Dropped: Done",
+        ]
+    );
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_parse_level() {
+    assert_eq!(pwn::parse_level("CRITICAL"), Some(Level::ERROR));
+    assert_eq!(pwn::parse_level("warning"), Some(Level::WARN));
+    assert_eq!(pwn::parse_level("debug"), Some(Level::DEBUG));
+    assert_eq!(pwn::parse_level("loud"), None);
+}
@@ -0,0 +1,14 @@
+// The logging subscriber is installed once per process, so this runs separately from the file
+// logging test, which initialises it with a file first.
+use std::io::ErrorKind;
+
+#[test]
+fn test_file_after_init() {
+    let path = std::env::temp_dir().join("pwn-test-logging-init.log");
+    let _ = std::fs::remove_file(&path);
+    pwn::init_logger();
+    let err = pwn::init_logger_with_file(&path).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::AlreadyExists);
+    // The file is never opened, rather than silently left unused
+    assert!(!path.exists());
+}
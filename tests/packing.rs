@@ -60,3 +60,147 @@ pub fn test_multi_threading() {
         h.join().unwrap();
     }
 }
+
+#[test]
+pub fn test_signed_packing() {
+    context::set_endianess(Little);
+    context::set_bits(pwn::Bits::ThirtyTwo);
+    assert_eq!(pack(-1), None);
+    assert_eq!(unpack(&[0xff; 4]), 0xffffffff);
+    context::set_signed(true);
+    assert_eq!(pack(-2).unwrap(), [0xfe, 0xff, 0xff, 0xff]);
+    assert_eq!(pack(0x80000000u32), None);
+    assert_eq!(unpack(&[0xff; 4]) as i64, -1);
+    assert_eq!(unpack(&[0xff, 0xff, 0xff, 0x7f]), 0x7fffffff);
+}
+
+#[test]
+pub fn test_explicit_packing() {
+    context::set_endianess(Little);
+    assert_eq!(p16_signed(-2), [0xfe, 0xff]);
+    assert_eq!(p64_signed(-1), [0xff; 8]);
+    assert_eq!(p16_with(0x1234, Big, Sign::Unsigned).unwrap(), [0x12, 0x34]);
+    assert_eq!(p64_with(-1, Big, Sign::Signed).unwrap(), [0xff; 8]);
+    assert_eq!(p8_with(0x80, Little, Sign::Signed), None);
+    assert_eq!(p8_with(-0x80, Little, Sign::Signed).unwrap(), [0x80]);
+    assert_eq!(p8_with(0x100, Little, Sign::Unsigned), None);
+
+    context::set_bits(pwn::Bits::SixtyFour);
+    assert_eq!(pack(-1), None);
+    assert_eq!(pack_signed(-1).unwrap(), [0xff; 8]);
+    assert_eq!(pack_signed(u64::MAX), None);
+    assert_eq!(
+        pack_bits(0xabcdef, 48).unwrap(),
+        [0xef, 0xcd, 0xab, 0, 0, 0]
+    );
+    assert_eq!(pack_bits(u128::MAX, 128).unwrap(), [0xff; 16]);
+    assert_eq!(pack_bits(1, 160).unwrap().len(), 20);
+    assert_eq!(pack_bits(1, 0), None);
+    context::set_signed(true);
+    assert_eq!(pack_bits(-1, 160).unwrap(), [0xff; 20]);
+    assert_eq!(pack_bits(0x800000, 24), None);
+    context::set_endianess(Big);
+    assert_eq!(pack_bits(-2, 24).unwrap(), [0xff, 0xff, 0xfe]);
+}
+
+#[test]
+pub fn test_short_unpacking() {
+    context::set_endianess(Little);
+    assert_eq!(u8(&[]), 0);
+    assert_eq!(u16(&[0x34]), 0x34);
+    assert_eq!(u32(&[0x78, 0x56]), 0x5678);
+    assert_eq!(u64(&[0x10, 0x32, 0x54, 0x76, 0xfe, 0x7f]), 0x7ffe76543210);
+    context::set_bits(pwn::Bits::SixtyFour);
+    assert_eq!(unpack(b"\x01"), 1);
+    context::set_endianess(Big);
+    assert_eq!(u32(&[0x56, 0x78]), 0x5678);
+    assert_eq!(u64(&[0x7f, 0xfe]), 0x7ffe);
+}
+
+#[test]
+pub fn test_padded_unpacking() {
+    context::set_endianess(Little);
+    assert_eq!(u16_padded(&[1]).unwrap(), 1);
+    assert_eq!(u32_padded(&[1, 2, 3, 4]).unwrap(), 0x04030201);
+    assert!(matches!(
+        u32_padded(&[0; 5]),
+        Err(pwn::Error::TooLong { len: 5, max: 4 })
+    ));
+    context::set_bits(pwn::Bits::ThirtyTwo);
+    assert_eq!(unpack_padded(b"AB").unwrap(), 0x4241);
+    assert!(unpack_padded(b"AAAAAAAA").is_err());
+
+    assert_eq!(
+        unpack_many(b"AAAABBBB", pwn::Bits::ThirtyTwo).unwrap(),
+        [0x41414141, 0x42424242]
+    );
+    assert_eq!(unpack_many(b"", pwn::Bits::SixtyFour).unwrap(), []);
+    assert!(matches!(
+        unpack_many(b"AAAABB", pwn::Bits::ThirtyTwo),
+        Err(pwn::Error::Misaligned {
+            len: 6,
+            word_size: 4
+        })
+    ));
+    context::set_signed(true);
+    assert_eq!(
+        unpack_many(&[0xff, 1], pwn::Bits::Eight).unwrap(),
+        [u64::MAX, 1]
+    );
+}
+
+#[test]
+pub fn test_leak_address() {
+    context::set_arch(pwn::AMD64);
+    // Addresses are never sign-extended
+    context::set_signed(true);
+    assert_eq!(
+        leak_address(b"\x10\x32\x54\xf6\xff\xff").unwrap(),
+        0xfffff6543210
+    );
+    assert_eq!(
+        leak_address(b"\x10\x32\x54\x76\xfe\x7f\n").unwrap(),
+        0x7ffe76543210
+    );
+    assert!(matches!(leak_address(b"\n"), Err(pwn::Error::EmptyLeak)));
+    assert!(leak_address(b"AAAAAAAAAAAA\n").is_err());
+    context::set_newline(b"\r\n");
+    assert_eq!(leak_address(b"\x10\x32\r\n").unwrap(), 0x3210);
+    context::set_arch(pwn::I386);
+    assert_eq!(leak_address(b"\x10\x32\x54\xf7").unwrap(), 0xf7543210);
+}
+
+#[test]
+pub fn test_bad_chars() {
+    context::set_arch(pwn::AMD64);
+    assert!(check_bad_chars(b"\n\0").is_ok());
+    context::set_bad_chars(*b"\n");
+    assert!(matches!(
+        check_bad_chars(b"AAAA\n"),
+        Err(pwn::Error::BadChar {
+            byte: b'\n',
+            offset: 4,
+            ..
+        })
+    ));
+    assert!(check_bad_chars_with(b"AAAA\n", b"\0").is_ok());
+    assert_eq!(pack_checked(0x401234).unwrap(), p64(0x401234));
+    assert!(matches!(
+        pack_checked(0x40120a),
+        Err(pwn::Error::BadChar { offset: 0, .. })
+    ));
+    assert!(matches!(
+        pack_checked_with(0x401234, b"\x12"),
+        Err(pwn::Error::BadChar { offset: 1, .. })
+    ));
+    assert_eq!(pack_checked_with(0x40120a, b"").unwrap(), p64(0x40120a));
+    context::set_bits(pwn::Bits::ThirtyTwo);
+    assert!(matches!(
+        pack_checked_with(u64::MAX, b""),
+        Err(pwn::Error::ValueTooLarge(pwn::Bits::ThirtyTwo))
+    ));
+    assert!(matches!(
+        pack_checked(u64::MAX),
+        Err(pwn::Error::ValueTooLarge(pwn::Bits::ThirtyTwo))
+    ));
+}
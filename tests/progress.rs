@@ -0,0 +1,89 @@
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use pwn::{info, init_logger, progress};
+
+#[derive(Clone, Default)]
+struct Capture(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Capture {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_interleaving() {
+    colored::control::set_override(false);
+    let capture = Capture::default();
+    pwn::__redirect_terminal(capture.clone());
+    init_logger();
+
+    let workers: Vec<_> = (0..4)
+        .map(|i| {
+            thread::spawn(move || {
+                let p = progress(format!("Worker {}", i));
+                for j in 0..20 {
+                    p.status(j);
+                    info!("worker {} step {}", i, j);
+                    thread::sleep(Duration::from_millis(5));
+                }
+                p.success("ok");
+            })
+        })
+        .collect();
+    for worker in workers {
+        worker.join().unwrap();
+    }
+    // Let the spinner thread notice that there are no bars left
+    thread::sleep(Duration::from_millis(300));
+
+    // Replay the output as a terminal would, where clearing removes the current line
+    let output = String::from_utf8(capture.0.lock().unwrap().clone()).unwrap();
+    let mut lines = Vec::new();
+    let mut statuses = Vec::new();
+    let mut current = String::new();
+    for (i, chunk) in output.split("\r\x1b[2K").enumerate() {
+        if i != 0 && !current.is_empty() {
+            statuses.push(std::mem::take(&mut current));
+        }
+        let mut parts = chunk.split('\n');
+        current.push_str(parts.next().unwrap());
+        for part in parts {
+            lines.push(std::mem::take(&mut current));
+            current.push_str(part);
+        }
+    }
+    // The status line is gone once every bar is finished
+    assert_eq!(current, "");
+
+    let mut steps = 0;
+    let mut finished = 0;
+    for line in &lines {
+        if line.starts_with("[*] progress: worker ") {
+            steps += 1;
+        } else if line.starts_with("[+] pwn::progress: Worker ") && line.ends_with(": ok") {
+            finished += 1;
+        } else {
+            panic!("Garbled line {:?}", line);
+        }
+    }
+    assert_eq!((steps, finished), (80, 4));
+
+    // The status line is laid out like the log lines, with a spinner as the prefix
+    assert!(!statuses.is_empty());
+    for status in &statuses {
+        let (spinner, bars) = status
+            .strip_prefix('[')
+            .and_then(|status| status.split_once("] pwn::progress: "))
+            .unwrap_or_else(|| panic!("Garbled status line {:?}", status));
+        assert!(["|", "/", "-", "\\"].contains(&spinner));
+        assert!(bars.split(" | ").all(|bar| bar.starts_with("Worker ")));
+    }
+}
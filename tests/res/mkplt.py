@@ -0,0 +1,260 @@
+#!/usr/bin/env python3
+"""Generate minimal dynamically-linked ELFs with the PLT layouts of architectures we can't
+compile for in CI. Each imports `foo`, `bar` and `baz` through a differently-shaped stub.
+
+Usage: python3 mkplt.py (writes plt-arm, plt-aarch64, plt-mips and plt-mipsel here)
+"""
+import os
+import struct
+
+EM_MIPS, EM_ARM, EM_AARCH64 = 8, 40, 183
+NAMES = [b"foo", b"bar", b"baz"]
+
+
+class Arch:
+    def __init__(self, machine, is_64, big, rela, reloc_type, base):
+        self.machine, self.is_64, self.big, self.rela = machine, is_64, big, rela
+        self.reloc_type, self.base = reloc_type, base
+        self.e = ">" if big else "<"
+        self.word = "Q" if is_64 else "I"
+        self.wsize = 8 if is_64 else 4
+
+    def pack(self, fmt, *args):
+        return struct.pack(self.e + fmt, *args)
+
+    def insns(self, *words):
+        return b"".join(self.pack("I", w) for w in words)
+
+
+def align(blob, n):
+    return blob + b"\0" * (-len(blob) % n)
+
+
+def build(arch, stubs_name, plt0, make_stubs, relocs=True, stub_symbols=False):
+    """`make_stubs(plt_addr, got_slots) -> (code, [stub addresses])`"""
+    a = arch
+    ehsize, phentsize, shentsize = (64, 56, 64) if a.is_64 else (52, 32, 40)
+    out = bytearray(b"\0" * (ehsize + 3 * phentsize))
+    sections = []  # (name, type, flags, offset, size, link, info, align, entsize)
+
+    def add(name, data, sh_type, flags=2, link=0, info=0, al=8, entsize=0):
+        nonlocal out
+        out = bytearray(align(bytes(out), al))
+        sections.append([name, sh_type, flags, len(out), len(data), link, info, al, entsize])
+        out += data
+        return len(sections), a.base + sections[-1][3]
+
+    interp = b"/lib/ld.so\0"
+    add(b".interp", interp, 1, al=1)
+    dynstr = b"\0" + b"".join(n + b"\0" for n in NAMES)
+    sym_size = 24 if a.is_64 else 16
+    # Symbol values are filled in once the stubs are placed
+    dynsym_idx, dynsym_addr = add(b".dynsym", b"\0" * sym_size * (len(NAMES) + 1), 11, info=1,
+                                  entsize=sym_size)
+    dynstr_idx, dynstr_addr = add(b".dynstr", dynstr, 3, al=1)
+    sections[dynsym_idx - 1][5] = dynstr_idx
+    nchain = len(NAMES) + 1
+    hash_idx, hash_addr = add(b".hash", a.pack("IIII" + "I" * nchain, 1, nchain, 0,
+                                               *([0] * (nchain + 1))), 5, link=dynsym_idx)
+    rel_size = (3 if a.rela else 2) * a.wsize
+    if relocs:
+        rel_idx, rel_addr = add(b".rela.plt" if a.rela else b".rel.plt",
+                                b"\0" * rel_size * len(NAMES), 4 if a.rela else 9,
+                                link=dynsym_idx, entsize=rel_size)
+    plt_size = len(plt0) + 64 * len(NAMES)
+    plt_idx, plt_addr = add(stubs_name, b"\0" * plt_size, 1, flags=6, al=16)
+    got_idx, got_addr = add(b".got", b"\0" * a.wsize * (3 + len(NAMES)), 1, flags=3)
+    slots = [got_addr + a.wsize * (3 + i) for i in range(len(NAMES))]
+    code, stub_addrs = make_stubs(plt_addr + len(plt0), slots)
+    code = plt0 + code
+    assert len(code) <= plt_size
+    start = sections[plt_idx - 1][3]
+    out[start:start + len(code)] = code
+    sections[plt_idx - 1][4] = len(code)
+
+    offset = 1
+    for i, (name, stub) in enumerate(zip(NAMES, stub_addrs)):
+        value = stub if stub_symbols else 0
+        if a.is_64:
+            sym = a.pack("IBBHQQ", offset, 0x12, 0, 0, value, 0)
+        else:
+            sym = a.pack("IIIBBH", offset, value, 0, 0x12, 0, 0)
+        pos = sections[dynsym_idx - 1][3] + sym_size * (i + 1)
+        out[pos:pos + sym_size] = sym
+        offset += len(name) + 1
+    if relocs:
+        for i, slot in enumerate(slots):
+            if a.is_64:
+                info = ((i + 1) << 32) | a.reloc_type
+            else:
+                info = ((i + 1) << 8) | a.reloc_type
+            rel = a.pack(a.word * 2, slot, info) + (a.pack(a.word, 0) if a.rela else b"")
+            pos = sections[rel_idx - 1][3] + rel_size * i
+            out[pos:pos + rel_size] = rel
+
+    dyns = [(4, hash_addr), (5, dynstr_addr), (6, dynsym_addr), (10, len(dynstr)),
+            (11, sym_size), (3, got_addr)]
+    if relocs:
+        dyns += [(23, rel_addr), (2, rel_size * len(NAMES)), (20, 7 if a.rela else 17)]
+    dyns.append((0, 0))
+    dynamic = b"".join(a.pack(a.word * 2, t, v) for t, v in dyns)
+    dyn_idx, dyn_addr = add(b".dynamic", dynamic, 6, flags=3, link=dynstr_idx,
+                            entsize=2 * a.wsize)
+
+    shstrtab = b"\0"
+    name_offsets = []
+    for s in sections:
+        name_offsets.append(len(shstrtab))
+        shstrtab += s[0] + b"\0"
+    name_offsets.append(len(shstrtab))
+    shstrtab += b".shstrtab\0"
+    add(b".shstrtab", shstrtab, 3, flags=0, al=1)
+    out = bytearray(align(bytes(out), 8))
+    shoff = len(out)
+    shdrs = [b"\0" * shentsize]
+    for name_off, (name, sh_type, flags, off, size, link, info, al, entsize) in zip(
+            name_offsets, sections):
+        addr = a.base + off if flags & 2 else 0
+        if a.is_64:
+            shdrs.append(a.pack("IIQQQQIIQQ", name_off, sh_type, flags, addr, off, size, link,
+                                info, al, entsize))
+        else:
+            shdrs.append(a.pack("IIIIIIIIII", name_off, sh_type, flags, addr, off, size, link,
+                                info, al, entsize))
+    out += b"".join(shdrs)
+
+    total = len(out)
+    interp_off = sections[0][3]
+    dyn_off = sections[dyn_idx - 1][3]
+    phdrs = [(3, interp_off, len(interp), 4), (1, 0, total, 7), (2, dyn_off, len(dynamic), 6)]
+    ph = b""
+    for p_type, off, size, flags in phdrs:
+        if a.is_64:
+            ph += a.pack("IIQQQQQQ", p_type, flags, off, a.base + off, a.base + off, size, size,
+                         0x1000 if p_type == 1 else 8)
+        else:
+            ph += a.pack("IIIIIIII", p_type, off, a.base + off, a.base + off, size, size, flags,
+                         0x1000 if p_type == 1 else 4)
+    ident = b"\x7fELF" + bytes([2 if a.is_64 else 1, 2 if a.big else 1, 1]) + b"\0" * 9
+    fmt = "HHIQQQIHHHHHH" if a.is_64 else "HHIIIIIHHHHHH"
+    header = ident + a.pack(fmt, 2, a.machine, 1, plt_addr, ehsize, shoff, 0, ehsize,
+                            phentsize, len(phdrs), shentsize, len(sections) + 1, len(sections))
+    out[:ehsize] = header
+    out[ehsize:ehsize + len(ph)] = ph
+    return bytes(out)
+
+
+def arm():
+    a = Arch(EM_ARM, False, False, False, 22, 0x10000)
+    # str lr, [sp, #-4]!; ldr lr, [pc, #4]; add lr, pc, lr; ldr pc, [lr, #8]!; .word 0
+    plt0 = a.insns(0xE52DE004, 0xE59FE004, 0xE08FE00E, 0xE5BEF008, 0)
+
+    def stubs(addr, slots):
+        code, addrs = b"", []
+        # foo: add ip, pc, #0xNN00000; add ip, ip, #0xNN000; ldr pc, [ip, #0xNNN]!
+        addrs.append(addr)
+        off = slots[0] - (addr + 8)
+        code += a.insns(0xE28FC600 | (off >> 20) & 0xFF, 0xE28CCA00 | (off >> 12) & 0xFF,
+                        0xE5BCF000 | off & 0xFFF)
+        # bar: the same, entered from Thumb through `bx pc; nop`
+        addrs.append(addr + len(code))
+        code += b"\x78\x47\xc0\x46"
+        pc = addr + len(code) + 8
+        off = slots[1] - pc
+        code += a.insns(0xE28FC600 | (off >> 20) & 0xFF, 0xE28CCA00 | (off >> 12) & 0xFF,
+                        0xE5BCF000 | off & 0xFFF)
+        # baz: ldr ip, [pc, #4]; add ip, ip, pc; ldr pc, [ip]; .word slot - .
+        addrs.append(addr + len(code))
+        off = slots[2] - (addr + len(code) + 12)
+        code += a.insns(0xE59FC004, 0xE08CC00F, 0xE59CF000, off & 0xFFFFFFFF)
+        return code, addrs
+
+    return build(a, b".plt", plt0, stubs)
+
+
+def aarch64():
+    a = Arch(EM_AARCH64, True, False, True, 1026, 0x400000)
+
+    def adrp(pc, target):
+        imm = ((target >> 12) - (pc >> 12)) & 0x1FFFFF
+        return 0x90000010 | (imm & 3) << 29 | (imm >> 2) << 5
+
+    def stub(pc, slot):
+        lo = slot & 0xFFF
+        return a.insns(adrp(pc, slot), 0xF9400211 | (lo // 8) << 10, 0x91000210 | lo << 10,
+                       0xD61F0220)
+
+    def plt0_at(addr, got):
+        # stp x16, x30, [sp, #-16]!; adrp x16, got; ldr x17, [x16, got[2]]; add; br x17; nops
+        return a.insns(0xA9BF7BF0) + stub(addr + 4, got + 16) + a.insns(0xD503201F) * 3
+
+    def stubs(addr, slots):
+        code, addrs = b"", []
+        addrs.append(addr)
+        code += stub(addr, slots[0])
+        # bar: with BTI
+        addrs.append(addr + len(code))
+        code += a.insns(0xD503245F) + stub(addr + len(code) + 4, slots[1]) + a.insns(0xD503201F)
+        addrs.append(addr + len(code))
+        code += stub(addr + len(code), slots[2])
+        return code, addrs
+
+    # The PLT header refers to the GOT, whose address isn't known until it is placed
+    first = build(a, b".plt", b"\0" * 32, stubs)
+    got = next(s for s in sections_of(first, a) if s[0] == b".got")[1]
+    plt = next(s for s in sections_of(first, a) if s[0] == b".plt")[1]
+    return build(a, b".plt", plt0_at(plt, got), stubs)
+
+
+def sections_of(blob, a):
+    """(name, addr) of each section in a generated ELF."""
+    shoff, = struct.unpack_from(a.e + "Q", blob, 0x28)
+    shnum, shstrndx = struct.unpack_from(a.e + "HH", blob, 0x3C)
+    headers = [struct.unpack_from(a.e + "IIQQQQIIQQ", blob, shoff + 64 * i) for i in range(shnum)]
+    strtab = headers[shstrndx][4]
+    for h in headers[1:]:
+        name = blob[strtab + h[0]:blob.index(b"\0", strtab + h[0])]
+        yield name, h[3]
+
+
+def mips():
+    # Based high in the address space so the `%lo` of the GOT slots is negative
+    a = Arch(EM_MIPS, False, True, False, 127, 0x4F8000)
+    # lui t8, %hi(got); lw t9, %lo(got)(t8); addiu t8, t8, %lo(got); subu t8, t8, gp; ...
+    plt0 = a.insns(0x3C180000, 0x8F190000, 0x27180000, 0x031CC023, 0x03E07825, 0x0018C082,
+                   0x0320F809, 0x2718FFFE)
+
+    def stubs(addr, slots):
+        code, addrs = b"", []
+        for slot in slots:
+            addrs.append(addr + len(code))
+            hi, lo = ((slot + 0x8000) >> 16) & 0xFFFF, slot & 0xFFFF
+            # lui t7, %hi; lw t9, %lo(t7); jr t9; addiu t8, t7, %lo
+            code += a.insns(0x3C0F0000 | hi, 0x8DF90000 | lo, 0x03200008, 0x25F80000 | lo)
+        return code, addrs
+
+    return build(a, b".plt", plt0, stubs)
+
+
+def mipsel():
+    a = Arch(EM_MIPS, False, False, False, 127, 0x400000)
+
+    def stubs(addr, slots):
+        code, addrs = b"", []
+        for i in range(2):
+            addrs.append(addr + len(code))
+            # lw t9, -0x7ff0(gp); move t7, ra; jalr t9; li t8, index
+            code += a.insns(0x8F998010, 0x03E07825, 0x0320F809, 0x24180001 + i)
+        # baz: the index built with lui/ori, as used for large symbol tables
+        addrs.append(addr + len(code))
+        code += a.insns(0x8F998010, 0x03E07825, 0x3C180000, 0x0320F809, 0x37180003)
+        return code, addrs
+
+    return build(a, b".MIPS.stubs", b"", stubs, relocs=False, stub_symbols=True)
+
+
+if __name__ == "__main__":
+    here = os.path.dirname(os.path.abspath(__file__))
+    for name, gen in [("arm", arm), ("aarch64", aarch64), ("mips", mips), ("mipsel", mipsel)]:
+        with open(os.path.join(here, "plt-" + name), "wb") as f:
+            f.write(gen())
@@ -25,3 +25,50 @@ fn listen_sock() {
     });
     assert_eq!(listener.recv().unwrap(), b"test");
 }
+
+/// Test that the context newline and timeout are honoured.
+#[test]
+fn context_newline_and_timeout() {
+    let mut listener = Listen::new(Some("127.0.0.1"), None).unwrap();
+    let port = listener.addr.port();
+    let client = std::thread::spawn(move || {
+        context::set_newline(*b"\r\n");
+        let mut sock = Remote::new("127.0.0.1", port).unwrap();
+        sock.sendline(*b"first").unwrap();
+        sock.sendline(*b"second").unwrap();
+        // Keep the connection open until the listener has timed out
+        sock.recvline().unwrap()
+    });
+    context::set_newline(*b"\r\n");
+    assert_eq!(listener.recvline().unwrap(), b"first\r\n");
+    assert_eq!(listener.recvline().unwrap(), b"second\r\n");
+
+    context::set_timeout(Some(Duration::from_millis(100)));
+    assert!(listener.recvline().is_err());
+    listener.sendline(*b"done").unwrap();
+    assert_eq!(client.join().unwrap(), b"done\r\n");
+}
+
+/// Test that data with bad characters is not sent.
+#[test]
+fn send_checked() {
+    let mut listener = Listen::new(Some("127.0.0.1"), None).unwrap();
+    let port = listener.addr.port();
+    let client = std::thread::spawn(move || {
+        context::set_bad_chars(*b"\n ");
+        let mut sock = Remote::new("127.0.0.1", port).unwrap();
+        let err = sock.send_checked(*b"bad data").unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        assert!(matches!(
+            err.into_inner().unwrap().downcast_ref(),
+            Some(pwn::Error::BadChar {
+                byte: b' ',
+                offset: 3,
+                ..
+            })
+        ));
+        sock.sendline_checked(*b"good").unwrap();
+    });
+    assert_eq!(listener.recvline().unwrap(), b"good\n");
+    client.join().unwrap();
+}